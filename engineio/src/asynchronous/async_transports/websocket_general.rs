@@ -43,7 +43,7 @@ impl AsyncWebsocketGeneralTransport {
 
         sender
             .send(Message::text(Cow::Borrowed(from_utf8(&Bytes::from(
-                Packet::new(PacketId::Ping, Bytes::from("probe")),
+                Packet::ping_probe(),
             ))?)))
             .await?;
 
@@ -52,7 +52,7 @@ impl AsyncWebsocketGeneralTransport {
             .await
             .ok_or(Error::IllegalWebsocketUpgrade())??;
 
-        if msg.into_data() != Bytes::from(Packet::new(PacketId::Pong, Bytes::from("probe"))) {
+        if msg.into_data() != Bytes::from(Packet::pong_probe()) {
             return Err(Error::InvalidPacket());
         }
 
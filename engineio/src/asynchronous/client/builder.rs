@@ -5,7 +5,7 @@ use crate::{
         callback::OptionalCallback,
         transport::AsyncTransport,
     },
-    error::Result,
+    error::{IncompleteReason, Result},
     header::HeaderMap,
     packet::HandshakePacket,
     Error, Packet, ENGINE_IO_VERSION,
@@ -127,9 +127,13 @@ impl ClientBuilder {
 
         let mut url = self.url.clone();
 
-        let handshake: HandshakePacket =
-            Packet::try_from(transport.next().await.ok_or(Error::IncompletePacket())??)?
-                .try_into()?;
+        let handshake: HandshakePacket = Packet::try_from(
+            transport
+                .next()
+                .await
+                .ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))??,
+        )?
+        .try_into()?;
 
         // update the base_url with the new sid
         url.query_pairs_mut().append_pair("sid", &handshake.sid[..]);
@@ -279,12 +283,6 @@ impl ClientBuilder {
             return Ok(false);
         }
 
-        Ok(self
-            .handshake
-            .as_ref()
-            .unwrap()
-            .upgrades
-            .iter()
-            .any(|upgrade| upgrade.to_lowercase() == *"websocket"))
+        Ok(self.handshake.as_ref().unwrap().supports_websocket())
     }
 }
@@ -7,6 +7,9 @@ mod callback;
 #[cfg(feature = "async")]
 pub mod client;
 mod generator;
+mod packet_reader;
+
+pub use packet_reader::PacketReader;
 
 #[cfg(feature = "async")]
 pub use client::Client;
@@ -0,0 +1,181 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::{Buf, BytesMut};
+use futures_util::{ready, Stream};
+use tokio::io::{AsyncRead, ReadBuf};
+
+use crate::{
+    error::Result,
+    packet::{Packet, Payload},
+};
+
+/// The number of bytes that are read from the underlying reader at once.
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// Reads engine.io packets one at a time from an [`AsyncRead`], splitting
+/// the stream at the revision 4 delimiter `\x1e`. Only the bytes of the
+/// packet that is currently assembled are buffered, so a long body doesn't
+/// have to be read into memory as a whole.
+#[derive(Debug)]
+pub struct PacketReader<R> {
+    reader: R,
+    buffer: BytesMut,
+    // bytes at the start of the buffer that are known to contain no delimiter
+    scanned: usize,
+    eof: bool,
+}
+
+impl<R: AsyncRead + Unpin> PacketReader<R> {
+    pub fn new(reader: R) -> Self {
+        PacketReader {
+            reader,
+            buffer: BytesMut::new(),
+            scanned: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns the underlying reader, bytes that were already read but not
+    /// decoded are lost.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Polls for the next packet. Returns `Poll::Pending` until a full
+    /// packet was read and `None` once the reader is exhausted.
+    pub fn poll_next_packet(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Packet>>> {
+        loop {
+            if let Some(frame) = self.next_frame() {
+                return Poll::Ready(Some(Packet::try_from(frame.freeze())));
+            }
+
+            if self.eof {
+                if self.buffer.is_empty() {
+                    return Poll::Ready(None);
+                }
+                // the last packet of a body isn't followed by a delimiter
+                self.scanned = 0;
+                return Poll::Ready(Some(Packet::try_from(self.buffer.split().freeze())));
+            }
+
+            let mut chunk = [0; READ_CHUNK_SIZE];
+            let mut read_buf = ReadBuf::new(&mut chunk);
+            if let Err(err) = ready!(Pin::new(&mut self.reader).poll_read(cx, &mut read_buf)) {
+                return Poll::Ready(Some(Err(err.into())));
+            }
+
+            match read_buf.filled() {
+                [] => self.eof = true,
+                filled => self.buffer.extend_from_slice(filled),
+            }
+        }
+    }
+
+    /// Splits off the bytes up to the next delimiter, if the buffer already
+    /// contains one.
+    fn next_frame(&mut self) -> Option<BytesMut> {
+        let separator = Payload::SEPARATOR as u8;
+        let Some(offset) = self.buffer[self.scanned..]
+            .iter()
+            .position(|&byte| byte == separator)
+        else {
+            self.scanned = self.buffer.len();
+            return None;
+        };
+
+        let frame = self.buffer.split_to(self.scanned + offset);
+        self.buffer.advance(1);
+        self.scanned = 0;
+        Some(frame)
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for PacketReader<R> {
+    type Item = Result<Packet>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_next_packet(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{collections::VecDeque, io};
+
+    use super::*;
+    use crate::{packet::PacketId, Error};
+    use bytes::Bytes;
+    use futures_util::StreamExt;
+
+    /// A reader that hands out one chunk per read and isn't ready in between.
+    struct ChunkedReader {
+        chunks: VecDeque<&'static [u8]>,
+        ready: bool,
+    }
+
+    impl AsyncRead for ChunkedReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if !self.ready {
+                self.ready = true;
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+
+            self.ready = false;
+            if let Some(chunk) = self.chunks.pop_front() {
+                buf.put_slice(chunk);
+            }
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn reader(chunks: &[&'static [u8]]) -> PacketReader<ChunkedReader> {
+        PacketReader::new(ChunkedReader {
+            chunks: chunks.iter().copied().collect(),
+            ready: false,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_split_frame() {
+        let mut packets = reader(&[b"4hel", b"lo\x1e4wor", b"ld"]);
+
+        let packet = packets.next().await.unwrap().unwrap();
+        assert_eq!(packet, Packet::new(PacketId::Message, "hello"));
+        let packet = packets.next().await.unwrap().unwrap();
+        assert_eq!(packet, Packet::new(PacketId::Message, "world"));
+        assert!(packets.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delimiter_at_chunk_boundary() {
+        let mut packets = reader(&[b"2probe\x1e", b"bAQIDBA==\x1e", b"6"]);
+
+        let packet = packets.next().await.unwrap().unwrap();
+        assert_eq!(packet, Packet::ping_probe());
+        let packet = packets.next().await.unwrap().unwrap();
+        assert_eq!(packet, Packet::binary(Bytes::from_static(&[1, 2, 3, 4])));
+        let packet = packets.next().await.unwrap().unwrap();
+        assert_eq!(packet.packet_id, PacketId::Noop);
+        assert!(packets.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_decode_error() {
+        let mut packets = reader(&[b"9oops\x1e4ok"]);
+
+        assert!(matches!(
+            packets.next().await,
+            Some(Err(Error::InvalidPacketId(_)))
+        ));
+        let packet = packets.next().await.unwrap().unwrap();
+        assert_eq!(packet, Packet::new(PacketId::Message, "ok"));
+    }
+}
@@ -275,13 +275,7 @@ impl ClientBuilder {
     /// Checks the handshake to see if websocket upgrades are allowed
     fn websocket_upgrade(&mut self) -> Result<bool> {
         // SAFETY: handshake set by above function.
-        Ok(self
-            .handshake
-            .as_ref()
-            .unwrap()
-            .upgrades
-            .iter()
-            .any(|upgrade| upgrade.to_lowercase() == *"websocket"))
+        Ok(self.handshake.as_ref().unwrap().supports_websocket())
     }
 }
 
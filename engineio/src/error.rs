@@ -1,6 +1,8 @@
+use crate::packet::PacketId;
 use base64::DecodeError;
 use reqwest::Error as ReqwestError;
 use serde_json::Error as JsonError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::io::Error as IoError;
 use std::str::Utf8Error;
 use thiserror::Error;
@@ -8,6 +10,23 @@ use tungstenite::Error as TungsteniteError;
 use url::ParseError as UrlParseError;
 
 /// Enumeration of all possible errors in the `socket.io` context.
+///
+/// The enum is `#[non_exhaustive]`, new variants may be added with any
+/// release. Code outside of this crate therefore has to match it with a
+/// wildcard arm:
+///
+/// ```rust
+/// use rust_engineio::Error;
+///
+/// fn is_malformed(error: &Error) -> bool {
+///     match error {
+///         Error::IncompletePacket(_) | Error::InvalidPacket() => true,
+///         _ => false,
+///     }
+/// }
+///
+/// assert!(is_malformed(&Error::InvalidPacket()));
+/// ```
 #[derive(Error, Debug)]
 #[non_exhaustive]
 #[cfg_attr(tarpaulin, ignore)]
@@ -16,10 +35,14 @@ pub enum Error {
     // Negative verb-object
     #[error("Invalid packet id: {0}")]
     InvalidPacketId(u8),
-    #[error("Error while parsing an incomplete packet")]
-    IncompletePacket(),
+    #[error("Error while parsing an incomplete packet: {0}")]
+    IncompletePacket(IncompleteReason),
     #[error("Got an invalid packet which did not follow the protocol format")]
     InvalidPacket(),
+    #[error("Packet length {0} exceeds the maximum packet length")]
+    PacketTooLarge(usize),
+    #[error("Unexpected packet type: {0:?}")]
+    UnexpectedPacketType(PacketId),
     #[error("An error occurred while decoding the utf-8 text: {0}")]
     InvalidUtf8(#[from] Utf8Error),
     #[error("An error occurred while encoding/decoding base64: {0}")]
@@ -48,12 +71,82 @@ pub enum Error {
     IncompleteIo(#[from] IoError),
     #[error("Server did not allow upgrading to websockets")]
     IllegalWebsocketUpgrade(),
+    #[error("Received an upgrade before the transport was probed")]
+    IllegalUpgradeBeforeProbe(),
     #[error("Invalid header name")]
     InvalidHeaderNameFromReqwest(#[from] reqwest::header::InvalidHeaderName),
     #[error("Invalid header value")]
     InvalidHeaderValueFromReqwest(#[from] reqwest::header::InvalidHeaderValue),
 }
 
+/// Describes where the input of an [`Error::IncompletePacket`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IncompleteReason {
+    /// There was no input at all.
+    EmptyInput,
+    /// The length of a revision 3 packet isn't followed by a `:`.
+    MissingLengthColon,
+    /// The input ended in the middle of a packet, or before all of its
+    /// parts arrived.
+    UnexpectedEnd,
+}
+
+impl Display for IncompleteReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            IncompleteReason::EmptyInput => "empty input",
+            IncompleteReason::MissingLengthColon => "missing ':' after the packet length",
+            IncompleteReason::UnexpectedEnd => "unexpected end of input",
+        })
+    }
+}
+
+/// The broad category of an [`Error`], e.g. to decide whether retrying
+/// makes sense without matching every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The input ended before a packet was complete.
+    Incomplete,
+    /// The input or the configuration doesn't follow the expected format.
+    Malformed,
+    /// The action isn't supported, or not in the current state.
+    Unsupported,
+    /// The underlying connection or a lock failed.
+    Io,
+}
+
+impl Error {
+    /// Returns the broad category of this error.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::IncompletePacket(_) => ErrorKind::Incomplete,
+            Error::InvalidPacketId(_)
+            | Error::InvalidPacket()
+            | Error::PacketTooLarge(_)
+            | Error::UnexpectedPacketType(_)
+            | Error::InvalidUtf8(_)
+            | Error::InvalidBase64(_)
+            | Error::InvalidUrl(_)
+            | Error::InvalidHandshake(_)
+            | Error::InvalidHttpConfiguration(_)
+            | Error::InvalidJson(_)
+            | Error::IllegalUpgradeBeforeProbe()
+            | Error::InvalidHeaderNameFromReqwest(_)
+            | Error::InvalidHeaderValueFromReqwest(_) => ErrorKind::Malformed,
+            Error::InvalidUrlScheme(_)
+            | Error::IllegalActionBeforeOpen()
+            | Error::IllegalWebsocketUpgrade() => ErrorKind::Unsupported,
+            Error::IncompleteResponseFromReqwest(_)
+            | Error::WebsocketError(_)
+            | Error::IncompleteHttp(_)
+            | Error::InvalidPoisonedLock()
+            | Error::IncompleteIo(_) => ErrorKind::Io,
+        }
+    }
+}
+
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 impl<T> From<std::sync::PoisonError<T>> for Error {
@@ -86,4 +179,118 @@ mod tests {
             std::io::Error::new(std::io::ErrorKind::Other, Error::IllegalWebsocketUpgrade());
         assert!(matches!(_io_error, _error));
     }
+
+    #[test]
+    fn test_error_display() {
+        let url_error = url::Url::parse("").unwrap_err();
+        let cases = [
+            (Error::InvalidPacketId(42), "Invalid packet id: 42"),
+            (
+                Error::IncompletePacket(IncompleteReason::MissingLengthColon),
+                "Error while parsing an incomplete packet: missing ':' after the packet length",
+            ),
+            (
+                Error::InvalidPacket(),
+                "Got an invalid packet which did not follow the protocol format",
+            ),
+            (
+                Error::PacketTooLarge(2048),
+                "Packet length 2048 exceeds the maximum packet length",
+            ),
+            (
+                Error::UnexpectedPacketType(PacketId::Message),
+                "Unexpected packet type: Message",
+            ),
+            (Error::InvalidUrl(url_error), "Invalid Url during parsing"),
+            (
+                Error::InvalidUrlScheme("ftp".to_owned()),
+                "Invalid Url Scheme: ftp",
+            ),
+            (
+                Error::IncompleteHttp(404),
+                "Network request returned with status code: 404",
+            ),
+            (
+                Error::InvalidHandshake("sid".to_owned()),
+                "Got illegal handshake response: sid",
+            ),
+            (
+                Error::IllegalActionBeforeOpen(),
+                "Called an action before the connection was established",
+            ),
+            (Error::InvalidPoisonedLock(), "A lock was poisoned"),
+            (
+                Error::IllegalWebsocketUpgrade(),
+                "Server did not allow upgrading to websockets",
+            ),
+            (
+                Error::IllegalUpgradeBeforeProbe(),
+                "Received an upgrade before the transport was probed",
+            ),
+            (
+                Error::WebsocketError(TungsteniteError::ConnectionClosed),
+                "Error with websocket connection: Connection closed normally",
+            ),
+        ];
+
+        for (error, message) in cases {
+            assert_eq!(error.to_string(), message);
+        }
+    }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(
+            Error::IncompletePacket(IncompleteReason::UnexpectedEnd).kind(),
+            ErrorKind::Incomplete
+        );
+        assert_eq!(Error::PacketTooLarge(2048).kind(), ErrorKind::Malformed);
+        assert_eq!(
+            Error::IllegalWebsocketUpgrade().kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(Error::IncompleteHttp(502).kind(), ErrorKind::Io);
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error as _;
+
+        let utf8_error = String::from_utf8(vec![0, 159]).unwrap_err().utf8_error();
+        let error = Error::from(utf8_error);
+        assert_eq!(
+            error.to_string(),
+            format!("An error occurred while decoding the utf-8 text: {utf8_error}")
+        );
+        assert_eq!(error.source().unwrap().to_string(), utf8_error.to_string());
+
+        let json_error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let message = json_error.to_string();
+        let error = Error::from(json_error);
+        assert_eq!(
+            error.to_string(),
+            format!("string is not json serializable: {message}")
+        );
+        assert_eq!(error.source().unwrap().to_string(), message);
+
+        let base64_error = DecodeError::InvalidLength;
+        let error = Error::from(base64_error.clone());
+        assert_eq!(
+            error.to_string(),
+            format!("An error occurred while encoding/decoding base64: {base64_error}")
+        );
+        assert_eq!(
+            error.source().unwrap().to_string(),
+            base64_error.to_string()
+        );
+
+        let io_error = IoError::other("oh no");
+        let error = Error::from(io_error);
+        assert_eq!(error.to_string(), "Got an IO-Error: oh no");
+        assert_eq!(error.source().unwrap().to_string(), "oh no");
+
+        assert!(Error::IncompletePacket(IncompleteReason::EmptyInput)
+            .source()
+            .is_none());
+    }
 }
@@ -2,28 +2,76 @@ extern crate base64;
 use base64::{engine::general_purpose, Engine as _};
 use bytes::{BufMut, Bytes, BytesMut};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::char;
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::fmt::{Display, Formatter, Result as FmtResult, Write};
 use std::ops::Index;
+use std::time::Duration;
 
-use crate::error::{Error, Result};
-/// Enumeration of the `engine.io` `Packet` types.
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+use crate::error::{Error, IncompleteReason, Result};
+
+/// The revision of the `engine.io` protocol, which determines how packets
+/// are framed inside of a [`Payload`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub enum ProtocolVersion {
+    /// Revision 3, prefixes every packet with its length (`<length>:<packet>`).
+    V3,
+    /// Revision 4, separates packets with the `\x1e` record separator.
+    #[default]
+    V4,
+}
+
+impl ProtocolVersion {
+    /// Returns whether the client drives the heartbeat by sending `Ping`
+    /// packets. In revision 3 the client pings and the server answers with a
+    /// `Pong`, since revision 4 the server pings and the client only answers.
+    pub fn client_sends_ping(self) -> bool {
+        self == ProtocolVersion::V3
+    }
+}
+
+/// Enumeration of the `engine.io` `Packet` types. With serde they are
+/// represented by their names, e.g. `"message_binary"`, which is unrelated to
+/// their encoding on the wire.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum PacketId {
+    #[serde(rename = "open")]
     Open,
+    #[serde(rename = "close")]
     Close,
+    #[serde(rename = "ping")]
     Ping,
+    #[serde(rename = "pong")]
     Pong,
+    #[serde(rename = "message")]
     Message,
     // A type of message that is base64 encoded
+    #[serde(rename = "message_binary")]
     MessageBinary,
+    #[serde(rename = "upgrade")]
     Upgrade,
+    #[serde(rename = "noop")]
     Noop,
 }
 
 impl PacketId {
+    /// Returns every [`PacketId`], ordered by their value.
+    pub fn all() -> &'static [PacketId] {
+        &[
+            PacketId::Open,
+            PacketId::Close,
+            PacketId::Ping,
+            PacketId::Pong,
+            PacketId::Message,
+            PacketId::MessageBinary,
+            PacketId::Upgrade,
+            PacketId::Noop,
+        ]
+    }
+
     /// Returns the byte that represents the [`PacketId`] as a [`char`].
     fn to_string_byte(self) -> u8 {
         match self {
@@ -87,6 +135,50 @@ pub struct HandshakePacket {
     pub ping_interval: u64,
     #[serde(rename = "pingTimeout")]
     pub ping_timeout: u64,
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+impl HandshakePacket {
+    /// Returns whether the `sid` is non-empty and only consists of the
+    /// url-safe base64 characters a server generates session ids from.
+    pub fn sid_is_valid(&self) -> bool {
+        !self.sid.is_empty()
+            && self
+                .sid
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    }
+
+    /// Returns the fields sent by the server that aren't known to this
+    /// crate, e.g. `maxPayload`, kept for forward compatibility.
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+
+    /// Returns whether the server offers an upgrade to `transport`. The
+    /// names are compared ignoring ASCII case.
+    pub fn supports_upgrade(&self, transport: &str) -> bool {
+        self.upgrades
+            .iter()
+            .any(|upgrade| upgrade.eq_ignore_ascii_case(transport))
+    }
+
+    /// Returns whether the server offers an upgrade to websockets.
+    pub fn supports_websocket(&self) -> bool {
+        self.supports_upgrade("websocket")
+    }
+
+    /// Returns the interval in which the server sends pings.
+    pub fn ping_interval_duration(&self) -> Duration {
+        Duration::from_millis(self.ping_interval)
+    }
+
+    /// Returns how long the server waits for a pong before it closes the
+    /// connection.
+    pub fn ping_timeout_duration(&self) -> Duration {
+        Duration::from_millis(self.ping_timeout)
+    }
 }
 
 impl TryFrom<Packet> for HandshakePacket {
@@ -97,6 +189,10 @@ impl TryFrom<Packet> for HandshakePacket {
 }
 
 impl Packet {
+    /// The body of the `Ping` and `Pong` packets exchanged before upgrading
+    /// the transport.
+    const PROBE: &'static [u8] = b"probe";
+
     /// Creates a new `Packet`.
     pub fn new<T: Into<Bytes>>(packet_id: PacketId, data: T) -> Self {
         Packet {
@@ -104,6 +200,174 @@ impl Packet {
             data: data.into(),
         }
     }
+
+    /// Creates a new `MessageBinary` `Packet`, which is base64 encoded when
+    /// it is sent as part of a payload.
+    pub fn binary<T: Into<Bytes>>(data: T) -> Self {
+        Packet::new(PacketId::MessageBinary, data)
+    }
+
+    /// Returns whether the data of this `Packet` needs to be base64 encoded.
+    pub fn is_binary(&self) -> bool {
+        self.packet_id == PacketId::MessageBinary
+    }
+
+    /// Deserializes the handshake carried by an `Open` packet. Any other
+    /// packet fails with [`Error::UnexpectedPacketType`].
+    pub fn as_handshake(&self) -> Result<HandshakePacket> {
+        if self.packet_id != PacketId::Open {
+            return Err(Error::UnexpectedPacketType(self.packet_id));
+        }
+        Ok(serde_json::from_slice(&self.data)?)
+    }
+
+    /// Returns the data of this `Packet` as text. Fails if the data isn't
+    /// valid UTF-8.
+    pub fn as_str(&self) -> Result<&str> {
+        Ok(std::str::from_utf8(&self.data)?)
+    }
+
+    /// Returns the decoded data of a binary message, and `None` for any other
+    /// packet, including text messages.
+    pub fn as_binary(&self) -> Option<&Bytes> {
+        self.is_binary().then_some(&self.data)
+    }
+
+    /// Turns a binary message into a text message. Fails if the data isn't
+    /// valid UTF-8, or if this `Packet` is no message.
+    pub fn force_text(mut self) -> Result<Self> {
+        match self.packet_id {
+            PacketId::Message => (),
+            PacketId::MessageBinary => {
+                std::str::from_utf8(&self.data)?;
+                self.packet_id = PacketId::Message;
+            }
+            _ => return Err(Error::InvalidPacket()),
+        }
+        Ok(self)
+    }
+
+    /// Turns a text message into a binary message, whose data gets base64
+    /// encoded once it is sent. Fails if this `Packet` is no message.
+    pub fn force_binary(mut self) -> Result<Self> {
+        match self.packet_id {
+            PacketId::Message | PacketId::MessageBinary => {
+                self.packet_id = PacketId::MessageBinary;
+                Ok(self)
+            }
+            _ => Err(Error::InvalidPacket()),
+        }
+    }
+
+    /// Returns a copy of this `Packet` whose data got decompressed by `f`.
+    /// The crate doesn't depend on any compression codec, so transports
+    /// that compress their data have to provide the decompressor.
+    pub fn decompress_with<F: Fn(&[u8]) -> Result<Bytes>>(&self, f: F) -> Result<Packet> {
+        Ok(Packet::new(self.packet_id, f(&self.data)?))
+    }
+
+    /// Creates the `Ping` packet that is sent to probe a new transport
+    /// before upgrading to it.
+    pub fn ping_probe() -> Self {
+        Packet::new(PacketId::Ping, Bytes::from_static(Self::PROBE))
+    }
+
+    /// Creates the `Pong` packet that answers a probing `Ping`.
+    pub fn pong_probe() -> Self {
+        Packet::new(PacketId::Pong, Bytes::from_static(Self::PROBE))
+    }
+
+    /// Returns whether this `Packet` carries the `probe` body used during
+    /// the upgrade handshake.
+    pub fn is_probe(&self) -> bool {
+        self.data == Self::PROBE
+    }
+
+    /// Creates a `Noop` packet, which a server sends to complete a dangling
+    /// polling request.
+    pub fn noop() -> Self {
+        Packet::new(PacketId::Noop, Bytes::new())
+    }
+
+    /// Creates a `Close` packet without a reason.
+    pub fn close() -> Self {
+        Packet::new(PacketId::Close, Bytes::new())
+    }
+
+    /// Creates a `Close` packet carrying `reason` as its body, to tell a
+    /// graceful close from an abrupt one.
+    pub fn close_with_reason(reason: &str) -> Self {
+        Packet::new(PacketId::Close, Bytes::copy_from_slice(reason.as_bytes()))
+    }
+
+    /// Returns the body of a `Close` packet, if it carries a UTF-8 reason.
+    pub fn close_reason(&self) -> Option<&str> {
+        if self.packet_id != PacketId::Close || self.data.is_empty() {
+            return None;
+        }
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// Tracks the packets received on a new transport while it gets upgraded.
+/// The transport has to be probed with a `Ping` carrying the `probe` body
+/// before an `Upgrade` packet is accepted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct UpgradeHandshake {
+    probed: bool,
+    upgraded: bool,
+}
+
+impl UpgradeHandshake {
+    /// Creates a handshake that neither got probed nor upgraded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handles a packet received on the new transport and returns the
+    /// packet that has to be sent in response, if any. Fails with
+    /// [`Error::IllegalUpgradeBeforeProbe`] if an `Upgrade` packet arrives
+    /// before the probe.
+    pub fn handle(&mut self, packet: &Packet) -> Result<Option<Packet>> {
+        match packet.packet_id {
+            PacketId::Ping if packet.is_probe() => {
+                self.probed = true;
+                Ok(Some(Packet::pong_probe()))
+            }
+            PacketId::Upgrade if !self.probed => Err(Error::IllegalUpgradeBeforeProbe()),
+            PacketId::Upgrade => {
+                self.upgraded = true;
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns whether the probe succeeded.
+    pub fn is_probed(&self) -> bool {
+        self.probed
+    }
+
+    /// Returns whether the transport got upgraded.
+    pub fn is_upgraded(&self) -> bool {
+        self.upgraded
+    }
+}
+
+impl Display for Packet {
+    /// Formats the packet type followed by its data, which is rendered as
+    /// text if it is valid UTF-8.
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match std::str::from_utf8(&self.data) {
+            Ok(data) => write!(f, "{:?}({data})", self.packet_id),
+            Err(_) => write!(
+                f,
+                "{:?}(<{} bytes binary>)",
+                self.packet_id,
+                self.data.len()
+            ),
+        }
+    }
 }
 
 impl TryFrom<Bytes> for Packet {
@@ -113,20 +377,20 @@ impl TryFrom<Bytes> for Packet {
         bytes: Bytes,
     ) -> std::result::Result<Self, <Self as std::convert::TryFrom<Bytes>>::Error> {
         if bytes.is_empty() {
-            return Err(Error::IncompletePacket());
+            return Err(Error::IncompletePacket(IncompleteReason::EmptyInput));
         }
 
-        let is_base64 = *bytes.first().ok_or(Error::IncompletePacket())? == b'b';
+        let is_base64 = bytes[0] == b'b';
 
         // only 'messages' packets could be encoded
         let packet_id = if is_base64 {
             PacketId::MessageBinary
         } else {
-            (*bytes.first().ok_or(Error::IncompletePacket())?).try_into()?
+            bytes[0].try_into()?
         };
 
         if bytes.len() == 1 && packet_id == PacketId::Message {
-            return Err(Error::IncompletePacket());
+            return Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd));
         }
 
         let data: Bytes = bytes.slice(1..);
@@ -156,42 +420,91 @@ impl From<Packet> for Bytes {
     }
 }
 
+/// A chain of packets as it is transmitted in a single polling request.
 #[derive(Debug, Clone)]
-pub(crate) struct Payload(Vec<Packet>);
+pub struct Payload(Vec<Packet>);
 
 impl Payload {
     // see https://en.wikipedia.org/wiki/Delimiter#ASCII_delimited_text
-    const SEPARATOR: char = '\x1e';
+    pub(crate) const SEPARATOR: char = '\x1e';
+
+    /// The maximum length a revision 3 packet may declare by default.
+    pub const DEFAULT_MAX_PACKET_LENGTH: usize = 1024 * 1024;
+
+    /// Creates a payload that transmits `packets` in the given order.
+    pub fn new(packets: Vec<Packet>) -> Self {
+        Payload(packets)
+    }
+
+    /// Decodes a `payload` framed as defined by the given [`ProtocolVersion`].
+    pub fn decode(payload: Bytes, version: ProtocolVersion) -> Result<Self> {
+        Self::decode_with_max_packet_length(payload, version, Self::DEFAULT_MAX_PACKET_LENGTH)
+    }
+
+    /// Decodes a `payload` like [`Payload::decode`], but fails with
+    /// [`Error::PacketTooLarge`] if a revision 3 packet declares a length
+    /// above `max_packet_length`.
+    pub fn decode_with_max_packet_length(
+        payload: Bytes,
+        version: ProtocolVersion,
+        max_packet_length: usize,
+    ) -> Result<Self> {
+        match version {
+            ProtocolVersion::V3 => Self::decode_v3(payload, max_packet_length),
+            ProtocolVersion::V4 => Self::decode_v4(payload),
+        }
+    }
+
+    /// Decodes a websocket frame, which always carries exactly one packet.
+    pub fn from_ws_frame(frame: Bytes) -> Result<Packet> {
+        Packet::try_from(frame)
+    }
 
-    #[cfg(test)]
+    /// Decodes the body of a polling response, which carries a chain of
+    /// packets framed as defined by the given [`ProtocolVersion`].
+    pub fn from_polling_body(body: Bytes, version: ProtocolVersion) -> Result<Vec<Packet>> {
+        Ok(Self::decode(body, version)?.0)
+    }
+
+    /// Encodes the packets framed as defined by the given [`ProtocolVersion`].
+    pub fn encode(self, version: ProtocolVersion) -> Result<Bytes> {
+        match version {
+            ProtocolVersion::V3 => self.encode_v3(),
+            ProtocolVersion::V4 => self.encode_v4(),
+        }
+    }
+
+    /// Returns the number of packets in the payload.
     pub fn len(&self) -> usize {
         self.0.len()
     }
-}
 
-impl TryFrom<Bytes> for Payload {
-    type Error = Error;
-    /// Decodes a `payload` which in the `engine.io` context means a chain of normal
-    /// packets separated by a certain SEPARATOR, in this case the delimiter `\x30`.
-    fn try_from(payload: Bytes) -> Result<Self> {
+    /// Returns whether the payload contains no packets.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Decodes a revision 4 payload, a chain of packets separated by the
+    /// delimiter `\x1e`.
+    fn decode_v4(payload: Bytes) -> Result<Self> {
         payload
             .split(|&c| c as char == Self::SEPARATOR)
             .map(|slice| Packet::try_from(payload.slice_ref(slice)))
             .collect::<Result<Vec<_>>>()
             .map(Self)
     }
-}
 
-impl TryFrom<Payload> for Bytes {
-    type Error = Error;
-    /// Encodes a payload. Payload in the `engine.io` context means a chain of
-    /// normal `packets` separated by a SEPARATOR, in this case the delimiter
-    /// `\x30`.
-    fn try_from(packets: Payload) -> Result<Self> {
+    /// Encodes a revision 4 payload, a chain of packets separated by the
+    /// delimiter `\x1e`.
+    fn encode_v4(self) -> Result<Bytes> {
+        if self.is_empty() {
+            return Ok(Bytes::new());
+        }
+
         let mut buf = BytesMut::new();
-        for packet in packets {
+        for packet in self {
             // at the moment no base64 encoding is used
-            buf.extend(Bytes::from(packet.clone()));
+            buf.extend(Bytes::from(packet));
             buf.put_u8(Payload::SEPARATOR as u8);
         }
 
@@ -199,6 +512,105 @@ impl TryFrom<Payload> for Bytes {
         let _ = buf.split_off(buf.len() - 1);
         Ok(buf.freeze())
     }
+
+    /// Encodes a revision 3 payload, a chain of packets which are each
+    /// prefixed by their length in bytes, followed by a `:`.
+    fn encode_v3(self) -> Result<Bytes> {
+        let mut buf = String::new();
+        for packet in self {
+            let encoded = if packet.is_binary() {
+                format!(
+                    "b{}{}",
+                    u8::from(PacketId::Message),
+                    general_purpose::STANDARD.encode(&packet.data)
+                )
+            } else {
+                format!("{}{}", packet.packet_id, std::str::from_utf8(&packet.data)?)
+            };
+
+            buf.push_str(&encoded.len().to_string());
+            buf.push(':');
+            buf.push_str(&encoded);
+        }
+
+        Ok(Bytes::from(buf))
+    }
+
+    /// Decodes a revision 3 payload, a chain of packets which are each
+    /// prefixed by their length in bytes, followed by a `:`.
+    fn decode_v3(payload: Bytes, max_packet_length: usize) -> Result<Self> {
+        let mut rest = std::str::from_utf8(&payload)?;
+        let mut packets = Vec::new();
+
+        while !rest.is_empty() {
+            let (length, tail) = rest.split_once(':').ok_or(Error::IncompletePacket(
+                IncompleteReason::MissingLengthColon,
+            ))?;
+            let length: usize = length.parse().map_err(|_| Error::InvalidPacket())?;
+            if length > max_packet_length {
+                return Err(Error::PacketTooLarge(length));
+            }
+
+            if tail.len() < length {
+                return Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd));
+            }
+            // a length that ends inside of a character can't be right
+            if !tail.is_char_boundary(length) {
+                return Err(Error::InvalidPacket());
+            }
+
+            let (packet, tail) = tail.split_at(length);
+            packets.push(Self::decode_v3_packet(&payload, packet)?);
+            rest = tail;
+        }
+
+        Ok(Self(packets))
+    }
+
+    /// Decodes a single packet of a revision 3 payload. Binary packets are
+    /// marked with a `b`, followed by their packet type and the base64
+    /// encoded data.
+    fn decode_v3_packet(payload: &Bytes, packet: &str) -> Result<Packet> {
+        let Some(encoded) = packet.strip_prefix('b') else {
+            return Packet::try_from(payload.slice_ref(packet.as_bytes()));
+        };
+
+        let mut chars = encoded.chars();
+        let packet_id = chars
+            .next()
+            .ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))?
+            .to_digit(10)
+            .ok_or(Error::InvalidPacket())?;
+
+        // only 'messages' packets could be encoded
+        if PacketId::try_from(packet_id as u8)? != PacketId::Message {
+            return Err(Error::InvalidPacket());
+        }
+
+        Ok(Packet::new(
+            PacketId::MessageBinary,
+            general_purpose::STANDARD.decode(chars.as_str())?,
+        ))
+    }
+}
+
+impl TryFrom<Bytes> for Payload {
+    type Error = Error;
+    /// Decodes a `payload` which in the `engine.io` context means a chain of normal
+    /// packets separated by a certain SEPARATOR, in this case the delimiter `\x30`.
+    fn try_from(payload: Bytes) -> Result<Self> {
+        Self::decode(payload, ProtocolVersion::V4)
+    }
+}
+
+impl TryFrom<Payload> for Bytes {
+    type Error = Error;
+    /// Encodes a payload. Payload in the `engine.io` context means a chain of
+    /// normal `packets` separated by a SEPARATOR, in this case the delimiter
+    /// `\x30`.
+    fn try_from(packets: Payload) -> Result<Self> {
+        packets.encode(ProtocolVersion::V4)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -223,6 +635,12 @@ impl IntoIterator for Payload {
     }
 }
 
+impl FromIterator<Packet> for Payload {
+    fn from_iter<T: IntoIterator<Item = Packet>>(packets: T) -> Self {
+        Payload(packets.into_iter().collect())
+    }
+}
+
 impl Index<usize> for Payload {
     type Output = Packet;
     fn index(&self, index: usize) -> &Packet {
@@ -264,6 +682,218 @@ mod tests {
         assert_eq!(Bytes::from(packet), data);
     }
 
+    #[test]
+    fn test_force_text_and_binary() -> Result<()> {
+        let text = Packet::new(PacketId::Message, Bytes::from_static(b"Hello"));
+
+        let binary = text.clone().force_binary()?;
+        assert_eq!(binary, Packet::binary(Bytes::from_static(b"Hello")));
+        assert_eq!(
+            Bytes::from(binary.clone()),
+            Bytes::from_static(b"bSGVsbG8=")
+        );
+        assert_eq!(binary.clone().force_binary()?, binary);
+
+        assert_eq!(binary.force_text()?, text);
+        assert_eq!(text.clone().force_text()?, text);
+
+        let sut = Packet::binary(Bytes::from_static(&[0, 159])).force_text();
+        assert!(matches!(sut, Err(Error::InvalidUtf8(_))));
+
+        let ping = Packet::new(PacketId::Ping, Bytes::new());
+        assert!(matches!(
+            ping.clone().force_text(),
+            Err(Error::InvalidPacket())
+        ));
+        assert!(matches!(ping.force_binary(), Err(Error::InvalidPacket())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_as_str() -> Result<()> {
+        let packet = Packet::new(PacketId::Message, Bytes::from("Hello 🙂"));
+        assert_eq!(packet.as_str()?, "Hello 🙂");
+
+        let packet = Packet::new(PacketId::Message, Bytes::from_static(&[0, 159, 146, 150]));
+        assert!(matches!(packet.as_str(), Err(Error::InvalidUtf8(_))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_as_binary() -> Result<()> {
+        let packet = Packet::try_from(Bytes::from_static(b"bSGVsbG8="))?;
+        assert_eq!(packet.as_binary(), Some(&Bytes::from_static(b"Hello")));
+
+        let packet = Packet::try_from(Bytes::from_static(b"4SGVsbG8="))?;
+        assert_eq!(packet.as_binary(), None);
+        assert_eq!(packet.as_str()?, "SGVsbG8=");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_noop() -> Result<()> {
+        assert_eq!(Bytes::from(Packet::noop()), Bytes::from_static(b"6"));
+        assert_eq!(Packet::try_from(Bytes::from_static(b"6"))?, Packet::noop());
+
+        let payload = Payload::new(vec![Packet::new(PacketId::Message, "x"), Packet::noop()]);
+        assert_eq!(
+            payload.clone().encode(ProtocolVersion::V4)?,
+            Bytes::from_static(b"4x\x1e6")
+        );
+        assert_eq!(
+            payload.encode(ProtocolVersion::V3)?,
+            Bytes::from_static(b"2:4x1:6")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_reason() -> Result<()> {
+        let close = Packet::close_with_reason("server shutting down");
+        assert_eq!(close.close_reason(), Some("server shutting down"));
+        assert_eq!(
+            Bytes::from(close.clone()),
+            Bytes::from_static(b"1server shutting down")
+        );
+        assert_eq!(
+            Packet::try_from(Bytes::from_static(b"1server shutting down"))?,
+            close
+        );
+
+        assert_eq!(Packet::close().close_reason(), None);
+        assert_eq!(Bytes::from(Packet::close()), Bytes::from_static(b"1"));
+
+        let message = Packet::new(PacketId::Message, Bytes::from_static(b"bye"));
+        assert_eq!(message.close_reason(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decompress_with() -> Result<()> {
+        let packet = Packet::new(PacketId::Message, Bytes::from_static(b"Hello"));
+
+        let identity = packet.decompress_with(|data| Ok(Bytes::copy_from_slice(data)))?;
+        assert_eq!(identity, packet);
+
+        let reversed = packet.decompress_with(|data| Ok(data.iter().rev().copied().collect()))?;
+        assert_eq!(reversed.packet_id, PacketId::Message);
+        assert_eq!(reversed.data, Bytes::from_static(b"olleH"));
+
+        let sut = packet.decompress_with(|_| Err(Error::InvalidPacket()));
+        assert!(matches!(sut, Err(Error::InvalidPacket())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_id_all() -> Result<()> {
+        assert_eq!(PacketId::all().len(), 8);
+
+        for &packet_id in PacketId::all() {
+            // a binary message has the value of a message on the wire
+            let expected = match packet_id {
+                PacketId::MessageBinary => PacketId::Message,
+                _ => packet_id,
+            };
+            assert_eq!(PacketId::try_from(u8::from(packet_id))?, expected);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packet_id_ordering() {
+        use std::collections::BTreeMap;
+
+        let ids = [
+            PacketId::Noop,
+            PacketId::Upgrade,
+            PacketId::MessageBinary,
+            PacketId::Message,
+            PacketId::Pong,
+            PacketId::Ping,
+            PacketId::Close,
+            PacketId::Open,
+        ];
+        let counters: BTreeMap<PacketId, u64> = ids.iter().map(|&id| (id, 1)).collect();
+
+        assert_eq!(
+            counters.keys().copied().collect::<Vec<_>>(),
+            ids.iter().rev().copied().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_is_binary() {
+        let packet = Packet::new(PacketId::Message, Bytes::from_static(b"Hello"));
+        assert!(!packet.is_binary());
+
+        let packet = Packet::binary(Bytes::from_static(b"Hello"));
+        assert!(packet.is_binary());
+        assert_eq!(packet.packet_id, PacketId::MessageBinary);
+        assert_eq!(Bytes::from(packet), Bytes::from_static(b"bSGVsbG8="));
+    }
+
+    #[test]
+    fn test_probe_packets() {
+        let packet = Packet::ping_probe();
+        assert!(packet.is_probe());
+        assert_eq!(Bytes::from(packet), Bytes::from_static(b"2probe"));
+
+        let packet = Packet::pong_probe();
+        assert!(packet.is_probe());
+        assert_eq!(Bytes::from(packet), Bytes::from_static(b"3probe"));
+
+        let packet = Packet::new(PacketId::Ping, Bytes::new());
+        assert!(!packet.is_probe());
+    }
+
+    #[test]
+    fn test_upgrade_handshake() {
+        let mut handshake = UpgradeHandshake::new();
+        let upgrade = Packet::new(PacketId::Upgrade, Bytes::new());
+
+        assert!(matches!(
+            handshake.handle(&upgrade),
+            Err(Error::IllegalUpgradeBeforeProbe())
+        ));
+        assert!(!handshake.is_upgraded());
+
+        // a regular ping is no probe
+        let ping = Packet::new(PacketId::Ping, Bytes::new());
+        assert_eq!(handshake.handle(&ping).unwrap(), None);
+        assert!(!handshake.is_probed());
+
+        assert_eq!(
+            handshake.handle(&Packet::ping_probe()).unwrap(),
+            Some(Packet::pong_probe())
+        );
+        assert!(handshake.is_probed());
+
+        assert_eq!(handshake.handle(&upgrade).unwrap(), None);
+        assert!(handshake.is_upgraded());
+    }
+
+    #[test]
+    fn test_packet_display() {
+        let packet = Packet::new(PacketId::Message, Bytes::from_static(b"hello"));
+        assert_eq!(packet.to_string(), "Message(hello)");
+
+        let packet = Packet::new(PacketId::Pong, Bytes::new());
+        assert_eq!(packet.to_string(), "Pong()");
+
+        let packet = Packet::new(
+            PacketId::MessageBinary,
+            Bytes::from_static(&[0xff, 0x00, 0x01]),
+        );
+        assert_eq!(packet.to_string(), "MessageBinary(<3 bytes binary>)");
+    }
+
     #[test]
     fn test_decode_payload() -> Result<()> {
         let data = Bytes::from_static(b"1Hello\x1e1HelloWorld");
@@ -280,6 +910,191 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_decode_payload_v3() -> Result<()> {
+        let data = Bytes::from_static(b"6:1Hello11:1HelloWorld");
+        let packets = Payload::decode(data, ProtocolVersion::V3)?;
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].packet_id, PacketId::Close);
+        assert_eq!(packets[0].data, Bytes::from_static(b"Hello"));
+        assert_eq!(packets[1].packet_id, PacketId::Close);
+        assert_eq!(packets[1].data, Bytes::from_static(b"HelloWorld"));
+
+        // the length counts bytes, the `€` takes three of them
+        let data = Bytes::from("4:4€1:2");
+        let packets = Payload::decode(data, ProtocolVersion::V3)?;
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].packet_id, PacketId::Message);
+        assert_eq!(packets[0].data, Bytes::from("€"));
+        assert_eq!(packets[1].packet_id, PacketId::Ping);
+
+        let sut = Payload::decode(Bytes::from("2:4€1:2"), ProtocolVersion::V3);
+        assert!(matches!(sut, Err(Error::InvalidPacket())));
+
+        let data = Bytes::from_static(b"1Hello\x1e1HelloWorld");
+        let packets = Payload::decode(data, ProtocolVersion::V4)?;
+        assert_eq!(packets.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_payload_v3_max_packet_length() -> Result<()> {
+        let sut = Payload::decode(Bytes::from_static(b"999999999999:4x"), ProtocolVersion::V3);
+        assert!(matches!(sut, Err(Error::PacketTooLarge(_))));
+
+        let data = Bytes::from_static(b"6:4Hello");
+        let sut = Payload::decode_with_max_packet_length(data.clone(), ProtocolVersion::V3, 5);
+        assert!(matches!(sut, Err(Error::PacketTooLarge(6))));
+
+        let packets = Payload::decode_with_max_packet_length(data, ProtocolVersion::V3, 6)?;
+        assert_eq!(packets[0].data, Bytes::from_static(b"Hello"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_payload_v3() -> Result<()> {
+        // SGVsbG8= is the encoded string for 'Hello'
+        let data = Bytes::from_static(b"10:b4SGVsbG8=6:4Hello");
+        let packets = Payload::decode(data, ProtocolVersion::V3)?;
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].packet_id, PacketId::MessageBinary);
+        assert_eq!(packets[0].data, Bytes::from_static(b"Hello"));
+        assert_eq!(packets[1].packet_id, PacketId::Message);
+        assert_eq!(packets[1].data, Bytes::from_static(b"Hello"));
+
+        let sut = Payload::decode(Bytes::from_static(b"10:bxSGVsbG8="), ProtocolVersion::V3);
+        assert!(matches!(sut, Err(Error::InvalidPacket())));
+
+        let sut = Payload::decode(Bytes::from_static(b"10:b2SGVsbG8="), ProtocolVersion::V3);
+        assert!(matches!(sut, Err(Error::InvalidPacket())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mixed_payload_v4() -> Result<()> {
+        let data = Bytes::from_static(b"4hello\x1ebSGVsbG8=");
+        let packets = Payload::decode(data.clone(), ProtocolVersion::V4)?;
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].packet_id, PacketId::Message);
+        assert_eq!(packets[0].data, Bytes::from_static(b"hello"));
+        assert_eq!(packets[1].packet_id, PacketId::MessageBinary);
+        assert_eq!(packets[1].data, Bytes::from_static(b"Hello"));
+
+        // only the binary packet gets base64 encoded
+        assert_eq!(packets.encode(ProtocolVersion::V4)?, data);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ws_frame_and_polling_body() -> Result<()> {
+        // a websocket frame is a single packet, even if it contains a separator
+        let packet = Payload::from_ws_frame(Bytes::from_static(b"4Hello\x1eWorld"))?;
+        assert_eq!(packet.packet_id, PacketId::Message);
+        assert_eq!(packet.data, Bytes::from_static(b"Hello\x1eWorld"));
+
+        let packets =
+            Payload::from_polling_body(Bytes::from_static(b"4Hello\x1e2"), ProtocolVersion::V4)?;
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].packet_id, PacketId::Message);
+        assert_eq!(packets[1].packet_id, PacketId::Ping);
+
+        let packets =
+            Payload::from_polling_body(Bytes::from_static(b"6:4Hello1:2"), ProtocolVersion::V3)?;
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].data, Bytes::from_static(b"Hello"));
+        assert_eq!(packets[1].packet_id, PacketId::Ping);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_payload_versions() -> Result<()> {
+        let packets = || {
+            Payload::decode(
+                Bytes::from_static(b"4Hello\x1ebSGVsbG8=\x1e2"),
+                ProtocolVersion::V4,
+            )
+        };
+
+        let v3 = packets()?.encode(ProtocolVersion::V3)?;
+        let v4 = packets()?.encode(ProtocolVersion::V4)?;
+        assert_eq!(v3, Bytes::from_static(b"6:4Hello10:b4SGVsbG8=1:2"));
+        assert_eq!(v4, Bytes::from_static(b"4Hello\x1ebSGVsbG8=\x1e2"));
+
+        // both round trip with their own framing
+        for (data, version) in [(v3, ProtocolVersion::V3), (v4, ProtocolVersion::V4)] {
+            let decoded = Payload::decode(data, version)?;
+            assert_eq!(decoded.len(), 3);
+            assert_eq!(decoded[0].packet_id, PacketId::Message);
+            assert_eq!(decoded[1].packet_id, PacketId::MessageBinary);
+            assert_eq!(decoded[1].data, Bytes::from_static(b"Hello"));
+            assert_eq!(decoded[2].packet_id, PacketId::Ping);
+        }
+
+        // the length counts bytes, not characters
+        let payload = Payload::decode(Bytes::from("4€"), ProtocolVersion::V4)?;
+        assert_eq!(payload.encode(ProtocolVersion::V3)?, Bytes::from("4:4€"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_incomplete_payload_v3() {
+        let sut = Payload::decode(Bytes::from_static(b"6:1Hell"), ProtocolVersion::V3);
+        assert!(matches!(
+            sut,
+            Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))
+        ));
+
+        let sut = Payload::decode(Bytes::from_static(b"61Hello"), ProtocolVersion::V3);
+        assert!(matches!(
+            sut,
+            Err(Error::IncompletePacket(
+                IncompleteReason::MissingLengthColon
+            ))
+        ));
+
+        let sut = Payload::decode(Bytes::from_static(b"x:1Hello"), ProtocolVersion::V3);
+        assert!(matches!(sut, Err(Error::InvalidPacket())));
+    }
+
+    #[test]
+    fn test_incomplete_reasons() {
+        let packets = [
+            (&b""[..], IncompleteReason::EmptyInput),
+            (b"4", IncompleteReason::UnexpectedEnd),
+        ];
+        for (packet, reason) in packets {
+            let sut = Packet::try_from(Bytes::from_static(packet));
+            assert!(
+                matches!(sut, Err(Error::IncompletePacket(r)) if r == reason),
+                "{packet:?}"
+            );
+        }
+
+        let payloads = [
+            (&b"5"[..], IncompleteReason::MissingLengthColon),
+            (b"2:4", IncompleteReason::UnexpectedEnd),
+            (b"1:b", IncompleteReason::UnexpectedEnd),
+            (b"0:", IncompleteReason::EmptyInput),
+        ];
+        for (payload, reason) in payloads {
+            let sut = Payload::decode(Bytes::from_static(payload), ProtocolVersion::V3);
+            assert!(
+                matches!(sut, Err(Error::IncompletePacket(r)) if r == reason),
+                "{payload:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_binary_payload() {
         let data = Bytes::from_static(b"bSGVsbG8=\x1ebSGVsbG9Xb3JsZA==\x1ebSGVsbG8=");
@@ -296,12 +1111,45 @@ mod tests {
         assert_eq!(Bytes::try_from(packets).unwrap(), data);
     }
 
+    #[test]
+    fn test_build_payload() -> Result<()> {
+        let packets = [
+            Packet::new(PacketId::Message, "Hello"),
+            Packet::binary(Bytes::from_static(b"World")),
+            Packet::ping_probe(),
+        ];
+
+        let payload: Payload = packets.iter().cloned().collect();
+        assert_eq!(payload.len(), 3);
+        assert_eq!(
+            Bytes::try_from(payload)?,
+            Bytes::from_static(b"4Hello\x1ebV29ybGQ=\x1e2probe")
+        );
+
+        let payload = Payload::new(packets.to_vec());
+        assert_eq!(
+            payload.encode(ProtocolVersion::V3)?,
+            Bytes::from_static(b"6:4Hello10:b4V29ybGQ=6:2probe")
+        );
+
+        // an empty payload encodes to nothing in both revisions
+        for version in [ProtocolVersion::V3, ProtocolVersion::V4] {
+            let payload: Payload = std::iter::empty().collect();
+            assert!(payload.is_empty());
+            assert_eq!(Payload::new(vec![]).encode(version)?, Bytes::new());
+            assert_eq!(payload.encode(version)?, Bytes::new());
+        }
+
+        Ok(())
+    }
+
     #[test]
     fn test_packet_id_conversion_and_incompl_packet() -> Result<()> {
         let sut = Packet::try_from(Bytes::from_static(b"4"));
-        assert!(sut.is_err());
-        let _sut = sut.unwrap_err();
-        assert!(matches!(Error::IncompletePacket, _sut));
+        assert!(matches!(
+            sut,
+            Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))
+        ));
 
         assert_eq!(PacketId::MessageBinary.to_string(), "b");
 
@@ -340,6 +1188,114 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_heartbeat_direction() {
+        assert!(ProtocolVersion::V3.client_sends_ping());
+        assert!(!ProtocolVersion::V4.client_sends_ping());
+        assert!(!ProtocolVersion::default().client_sends_ping());
+    }
+
+    #[test]
+    fn test_handshake_sid_is_valid() {
+        let mut packet = HandshakePacket {
+            ping_interval: 10000,
+            ping_timeout: 1000,
+            sid: "lv_VI97HAXpY6yYWAAAC".to_owned(),
+            upgrades: vec!["websocket".to_owned()],
+            extra: HashMap::new(),
+        };
+        assert!(packet.sid_is_valid());
+
+        packet.sid = "a-b_C9".to_owned();
+        assert!(packet.sid_is_valid());
+
+        for sid in ["", "abc def", "abc/def", "abc+def", "abc=", "sid™"] {
+            packet.sid = sid.to_owned();
+            assert!(!packet.sid_is_valid(), "{sid:?} should be invalid");
+        }
+    }
+
+    #[test]
+    fn test_packet_id_serde() {
+        let json = serde_json::to_string(&PacketId::MessageBinary).unwrap();
+        assert_eq!(json, "\"message_binary\"");
+        assert_eq!(
+            serde_json::from_str::<PacketId>(&json).unwrap(),
+            PacketId::MessageBinary
+        );
+
+        assert_eq!(serde_json::to_string(&PacketId::Open).unwrap(), "\"open\"");
+        assert!(serde_json::from_str::<PacketId>("0").is_err());
+    }
+
+    #[test]
+    fn test_handshake_packet_extra_fields() {
+        let data = r#"{"sid":"Test","upgrades":[],"pingInterval":25000,"pingTimeout":20000,"maxPayload":1000000,"future":{"a":1}}"#;
+        let packet = HandshakePacket::try_from(Packet::new(PacketId::Open, data)).unwrap();
+
+        assert_eq!(packet.sid, "Test");
+        assert_eq!(packet.ping_interval, 25000);
+        assert_eq!(packet.extra().len(), 2);
+        assert_eq!(packet.extra()["maxPayload"], serde_json::json!(1000000));
+        assert_eq!(packet.extra()["future"], serde_json::json!({ "a": 1 }));
+
+        // the extra fields are kept when the packet is encoded again
+        let encoded = serde_json::to_string(&packet).unwrap();
+        assert_eq!(
+            HandshakePacket::try_from(Packet::new(PacketId::Open, encoded)).unwrap(),
+            packet
+        );
+    }
+
+    #[test]
+    fn test_handshake_supports_upgrade() {
+        let mut packet = HandshakePacket {
+            ping_interval: 25000,
+            ping_timeout: 20000,
+            sid: "Test".to_owned(),
+            upgrades: vec!["websocket".to_owned()],
+            extra: HashMap::new(),
+        };
+        assert!(packet.supports_websocket());
+        assert!(packet.supports_upgrade("websocket"));
+        assert!(!packet.supports_upgrade("webtransport"));
+        assert!(packet.supports_upgrade("WebSocket"));
+
+        packet.upgrades = vec!["WebSocket".to_owned()];
+        assert!(packet.supports_websocket());
+
+        packet.upgrades.clear();
+        assert!(!packet.supports_websocket());
+    }
+
+    #[test]
+    fn test_handshake_durations() {
+        let data = r#"{"sid":"Test","upgrades":[],"pingInterval":25000,"pingTimeout":20000}"#;
+        let packet = HandshakePacket::try_from(Packet::new(PacketId::Open, data)).unwrap();
+
+        assert_eq!(packet.ping_interval_duration(), Duration::from_secs(25));
+        assert_eq!(packet.ping_timeout_duration(), Duration::from_secs(20));
+    }
+
+    #[test]
+    fn test_as_handshake() {
+        let data =
+            r#"{"sid":"Test","upgrades":["websocket"],"pingInterval":25000,"pingTimeout":20000}"#;
+
+        let handshake = Packet::new(PacketId::Open, data).as_handshake().unwrap();
+        assert_eq!(handshake.sid, "Test");
+        assert!(handshake.supports_websocket());
+
+        assert!(matches!(
+            Packet::new(PacketId::Message, data).as_handshake(),
+            Err(Error::UnexpectedPacketType(PacketId::Message))
+        ));
+        assert!(matches!(
+            Packet::new(PacketId::Open, "{}").as_handshake(),
+            Err(Error::InvalidJson(_))
+        ));
+    }
+
     #[test]
     fn test_handshake_packet() {
         assert!(
@@ -350,6 +1306,7 @@ mod tests {
             ping_timeout: 1000,
             sid: "Test".to_owned(),
             upgrades: vec!["websocket".to_owned(), "test".to_owned()],
+            extra: HashMap::new(),
         };
         let encoded: String = serde_json::to_string(&packet).unwrap();
 
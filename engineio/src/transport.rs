@@ -1,5 +1,6 @@
 use super::transports::{PollingTransport, WebsocketSecureTransport, WebsocketTransport};
 use crate::error::Result;
+use crate::packet::Packet;
 use adler32::adler32;
 use bytes::Bytes;
 use std::time::SystemTime;
@@ -32,6 +33,16 @@ pub trait Transport {
     }
 }
 
+/// Exchanges whole `engine.io` packets instead of raw frames, so that the
+/// code above it doesn't depend on how a transport frames them.
+pub trait PacketTransport {
+    /// Sends the packets to the other side, in order.
+    fn send(&self, packets: Vec<Packet>) -> Result<()>;
+
+    /// Receives the next batch of packets from the other side.
+    fn recv(&self) -> Result<Vec<Packet>>;
+}
+
 #[derive(Debug)]
 pub enum TransportType {
     Polling(PollingTransport),
@@ -72,3 +83,43 @@ impl std::fmt::Debug for dyn Transport {
         f.write_fmt(format_args!("Transport(base_url: {:?})", self.base_url(),))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::packet::PacketId;
+    use std::sync::Mutex;
+
+    /// A transport that echoes all packets sent through it.
+    #[derive(Debug, Default)]
+    struct MockTransport {
+        queue: Mutex<Vec<Packet>>,
+    }
+
+    impl PacketTransport for MockTransport {
+        fn send(&self, packets: Vec<Packet>) -> Result<()> {
+            self.queue.lock()?.extend(packets);
+            Ok(())
+        }
+
+        fn recv(&self) -> Result<Vec<Packet>> {
+            Ok(std::mem::take(&mut *self.queue.lock()?))
+        }
+    }
+
+    #[test]
+    fn test_packet_transport() -> Result<()> {
+        let transport = MockTransport::default();
+        let packets = vec![
+            Packet::new(PacketId::Message, Bytes::from_static(b"hello")),
+            Packet::binary(Bytes::from_static(&[1, 2, 3])),
+            Packet::new(PacketId::Ping, Bytes::new()),
+        ];
+
+        transport.send(packets.clone())?;
+        assert_eq!(transport.recv()?, packets);
+        assert!(transport.recv()?.is_empty());
+
+        Ok(())
+    }
+}
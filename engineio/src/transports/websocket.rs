@@ -2,7 +2,7 @@ use crate::{
     asynchronous::{
         async_transports::WebsocketTransport as AsyncWebsocketTransport, transport::AsyncTransport,
     },
-    error::Result,
+    error::{IncompleteReason, Result},
     transport::Transport,
     Error,
 };
@@ -50,8 +50,8 @@ impl Transport for WebsocketTransport {
         self.runtime.block_on(async {
             let r = self.inner.poll_next().await;
             match r {
-                Ok(b) => b.ok_or(Error::IncompletePacket()),
-                Err(_) => Err(Error::IncompletePacket()),
+                Ok(b) => b.ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd)),
+                Err(_) => Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd)),
             }
         })
     }
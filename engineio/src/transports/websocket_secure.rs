@@ -3,7 +3,7 @@ use crate::{
         async_transports::WebsocketSecureTransport as AsyncWebsocketSecureTransport,
         transport::AsyncTransport,
     },
-    error::Result,
+    error::{IncompleteReason, Result},
     transport::Transport,
     Error,
 };
@@ -58,8 +58,8 @@ impl Transport for WebsocketSecureTransport {
         self.runtime.block_on(async {
             let r = self.inner.poll_next().await;
             match r {
-                Ok(b) => b.ok_or(Error::IncompletePacket()),
-                Err(_) => Err(Error::IncompletePacket()),
+                Ok(b) => b.ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd)),
+                Err(_) => Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd)),
             }
         })
     }
@@ -0,0 +1,14 @@
+#![no_main]
+
+use bytes::Bytes;
+use libfuzzer_sys::fuzz_target;
+use rust_socketio::packet::Packet;
+use std::convert::TryFrom;
+
+// decoding arbitrary input must fail cleanly instead of panicking, and
+// every packet that decodes must encode again
+fuzz_target!(|data: &[u8]| {
+    if let Ok(packet) = Packet::try_from(&Bytes::copy_from_slice(data)) {
+        let _ = Bytes::from(&packet);
+    }
+});
@@ -0,0 +1,118 @@
+//! Compares the heap allocations needed to build and encode a packet with a
+//! single attachment, depending on whether the attachments are stored in a
+//! `Vec` or inline in a `SmallVec`.
+
+use bytes::Bytes;
+use rust_socketio::packet::{Packet, PacketId};
+use rust_socketio::parser::Encoder;
+use smallvec::{smallvec, SmallVec};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const ITERATIONS: usize = 10_000;
+
+/// Returns the average number of allocations of a single call to `f`.
+fn allocations_per_call<F: FnMut()>(mut f: F) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / ITERATIONS as f64
+}
+
+/// A prototype of a packet that stores its attachments inline. The header
+/// is a `Packet` without attachments, its placeholders only depend on the
+/// `attachment_count`.
+struct InlinePacket {
+    header: Packet,
+    attachments: SmallVec<[Bytes; 1]>,
+}
+
+/// Encodes the header followed by the attachments. The frames are
+/// allocated up front, the same way for both storages.
+fn encode<'a, I>(header: &Packet, attachments: I) -> Vec<Bytes>
+where
+    I: ExactSizeIterator<Item = &'a Bytes>,
+{
+    let mut frames = Vec::with_capacity(1 + attachments.len());
+    frames.push(Bytes::from(header));
+    frames.extend(attachments.cloned());
+    frames
+}
+
+fn vec_packet(attachment: &Bytes) -> Packet {
+    Packet::new(
+        PacketId::BinaryEvent,
+        "/".to_owned(),
+        Some(String::from("\"upload\"")),
+        None,
+        1,
+        Some(vec![attachment.clone()]),
+    )
+}
+
+fn inline_packet(attachment: &Bytes) -> InlinePacket {
+    InlinePacket {
+        header: Packet::new(
+            PacketId::BinaryEvent,
+            "/".to_owned(),
+            Some(String::from("\"upload\"")),
+            None,
+            1,
+            None,
+        ),
+        attachments: smallvec![attachment.clone()],
+    }
+}
+
+fn encode_vec(packet: &Packet) -> Vec<Bytes> {
+    encode(
+        packet,
+        packet.attachments.as_deref().unwrap_or_default().iter(),
+    )
+}
+
+fn encode_inline(packet: &InlinePacket) -> Vec<Bytes> {
+    encode(&packet.header, packet.attachments.iter())
+}
+
+fn main() {
+    // a static buffer, so that cloning the attachment doesn't allocate
+    let attachment = Bytes::from_static(&[0; 64]);
+
+    // both storages have to produce the frames the socket sends
+    let frames = Encoder::encode(&vec_packet(&attachment));
+    assert_eq!(encode_vec(&vec_packet(&attachment)), frames);
+    assert_eq!(encode_inline(&inline_packet(&attachment)), frames);
+
+    let vec = allocations_per_call(|| {
+        black_box(encode_vec(&vec_packet(&attachment)));
+    });
+
+    let inline = allocations_per_call(|| {
+        black_box(encode_inline(&inline_packet(&attachment)));
+    });
+
+    println!("allocations per encoded single-attachment packet");
+    println!("  Vec<Bytes>:            {vec:.2}");
+    println!("  SmallVec<[Bytes; 1]>:  {inline:.2}");
+}
@@ -0,0 +1,51 @@
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_socketio::packet::Packet;
+
+/// A fixed corpus of the packets that are received most often.
+fn corpus() -> Vec<Bytes> {
+    vec![
+        Bytes::from_static(b"2[\"hello\",{\"user\":\"alice\",\"count\":42}]"),
+        Bytes::from_static(b"2/admin,456[\"project:delete\",123]"),
+        Bytes::from_static(b"3/admin,456[\"ok\"]"),
+        Bytes::from_static(b"0/admin,{\"sid\":\"oSO0OpakMV_3jnilAAAA\"}"),
+        Bytes::from_static(b"51-[\"upload\",{\"_placeholder\":true,\"num\":0}]"),
+    ]
+}
+
+pub fn criterion_decode(c: &mut Criterion) {
+    let corpus = corpus();
+
+    c.bench_function("decode packets with try_from", |b| {
+        b.iter(|| {
+            for frame in &corpus {
+                black_box(Packet::try_from(black_box(frame)).unwrap());
+            }
+        })
+    });
+
+    c.bench_function("decode packets with decode_into", |b| {
+        let mut packet = Packet::default();
+        b.iter(|| {
+            for frame in &corpus {
+                packet.decode_into(black_box(frame)).unwrap();
+                black_box(&packet);
+            }
+        })
+    });
+}
+
+pub fn criterion_decode_binary_event(c: &mut Criterion) {
+    let frame = Bytes::from_static(
+        b"53-/admin,456[\"upload\",{\"name\":\"a.png\",\"size\":1024},[1,2,3],\"tag\",\
+        {\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1},\
+        {\"_placeholder\":true,\"num\":2}]",
+    );
+
+    c.bench_function("decode binary event with several args", |b| {
+        b.iter(|| black_box(Packet::try_from(black_box(&frame)).unwrap()))
+    });
+}
+
+criterion_group!(benches, criterion_decode, criterion_decode_binary_event);
+criterion_main!(benches);
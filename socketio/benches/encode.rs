@@ -0,0 +1,60 @@
+use bytes::Bytes;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_socketio::packet::{Packet, PacketId};
+
+/// A mix of the packets that are sent most often.
+fn packets() -> Vec<Packet> {
+    vec![
+        Packet::new(
+            PacketId::Event,
+            "/".to_owned(),
+            Some(String::from(
+                "[\"hello\",{\"user\":\"alice\",\"count\":42}]",
+            )),
+            None,
+            0,
+            None,
+        ),
+        Packet::new(
+            PacketId::Event,
+            "/admin".to_owned(),
+            Some(String::from("[\"project:delete\",123]")),
+            Some(456),
+            0,
+            None,
+        ),
+        Packet::new(
+            PacketId::BinaryEvent,
+            "/".to_owned(),
+            Some(String::from("\"upload\"")),
+            None,
+            1,
+            Some(vec![Bytes::from_static(&[0; 64])]),
+        ),
+        Packet::new(
+            PacketId::BinaryEvent,
+            "/admin".to_owned(),
+            Some(String::from("\"upload\"")),
+            Some(789),
+            2,
+            Some(vec![
+                Bytes::from_static(&[1; 64]),
+                Bytes::from_static(&[2; 64]),
+            ]),
+        ),
+    ]
+}
+
+pub fn criterion_encode(c: &mut Criterion) {
+    let packets = packets();
+    c.bench_function("encode event and binary event packets", |b| {
+        b.iter(|| {
+            for packet in &packets {
+                black_box(Bytes::from(black_box(packet)));
+            }
+        })
+    });
+}
+
+criterion_group!(benches, criterion_encode);
+criterion_main!(benches);
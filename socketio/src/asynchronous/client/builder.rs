@@ -6,9 +6,15 @@ use rust_engineio::{
     header::{HeaderMap, HeaderValue},
 };
 use std::collections::HashMap;
+use std::sync::Arc;
 use url::Url;
 
-use crate::{error::Result, Error, Event, Payload, TransportType};
+use crate::{
+    error::Result,
+    packet::Namespace,
+    parser::{DefaultParser, Parser},
+    Error, Event, Payload, TransportType,
+};
 
 use super::{
     callback::{Callback, DynAsyncAnyCallback, DynAsyncCallback},
@@ -29,6 +35,7 @@ pub struct ClientBuilder {
     opening_headers: Option<HeaderMap>,
     transport_type: TransportType,
     auth: Option<serde_json::Value>,
+    parser: Arc<dyn Parser + Send + Sync>,
 }
 
 impl ClientBuilder {
@@ -79,6 +86,7 @@ impl ClientBuilder {
             opening_headers: None,
             transport_type: TransportType::Any,
             auth: None,
+            parser: Arc::new(DefaultParser),
         }
     }
 
@@ -86,6 +94,8 @@ impl ClientBuilder {
     /// with a leading `/`. Valid examples are e.g. `/admin`, `/foo`.
     /// If the String provided doesn't start with a leading `/`, it is
     /// added manually.
+    /// A namespace that contains a `,` makes connecting fail with
+    /// [`crate::Error::InvalidNamespace`].
     pub fn namespace<T: Into<String>>(mut self, namespace: T) -> Self {
         let mut nsp = namespace.into();
         if !nsp.starts_with('/') {
@@ -309,6 +319,29 @@ impl ClientBuilder {
         self
     }
 
+    /// Specifies the [`Parser`] that packets are encoded and decoded with,
+    /// which has to match the parser the server uses. Defaults to
+    /// [`DefaultParser`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use rust_socketio::{asynchronous::ClientBuilder, parser::DefaultParser};
+    ///
+    /// #[tokio::main]
+    /// async fn main() {
+    ///     let socket = ClientBuilder::new("http://localhost:4200/")
+    ///         .parser(DefaultParser)
+    ///         .connect()
+    ///         .await
+    ///         .expect("connection failed");
+    /// }
+    /// ```
+    pub fn parser<P: Parser + Send + Sync + 'static>(mut self, parser: P) -> Self {
+        self.parser = Arc::new(parser);
+
+        self
+    }
+
     /// Connects the socket to a certain endpoint. This returns a connected
     /// [`Client`] instance. This method returns an [`std::result::Result::Err`]
     /// value if something goes wrong during connection. Also starts a separate
@@ -360,6 +393,8 @@ impl ClientBuilder {
 
     //TODO: 0.3.X stabilize
     pub(crate) async fn connect_manual(self) -> Result<Client> {
+        let namespace = Namespace::new(self.namespace)?;
+
         // Parse url here rather than in new to keep new returning Self.
         let mut url = Url::parse(&self.address)?;
 
@@ -383,15 +418,9 @@ impl ClientBuilder {
             TransportType::WebsocketUpgrade => builder.build_websocket_with_upgrade().await?,
         };
 
-        let inner_socket = InnerSocket::new(engine_client)?;
+        let inner_socket = InnerSocket::new(engine_client, self.parser)?;
 
-        let socket = Client::new(
-            inner_socket,
-            &self.namespace,
-            self.on,
-            self.on_any,
-            self.auth,
-        )?;
+        let socket = Client::new(inner_socket, namespace, self.on, self.on_any, self.auth)?;
         socket.connect().await?;
 
         Ok(socket)
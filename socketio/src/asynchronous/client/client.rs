@@ -2,7 +2,6 @@ use std::{collections::HashMap, ops::DerefMut, pin::Pin, sync::Arc};
 
 use futures_util::{future::BoxFuture, stream, Stream, StreamExt};
 use log::trace;
-use rand::{thread_rng, Rng};
 use serde_json::Value;
 use tokio::{
     sync::RwLock,
@@ -15,8 +14,8 @@ use super::{
 };
 use crate::{
     asynchronous::socket::Socket as InnerSocket,
-    error::{Error, Result},
-    packet::{Packet, PacketId},
+    error::{Error, IncompleteReason, Result},
+    packet::{AckIdGenerator, Packet, PacketId},
     Event, Payload,
 };
 
@@ -30,6 +29,7 @@ pub struct Client {
     on: Arc<RwLock<HashMap<Event, Callback<DynAsyncCallback>>>>,
     on_any: Arc<RwLock<Option<Callback<DynAsyncAnyCallback>>>>,
     outstanding_acks: Arc<RwLock<Vec<Ack>>>,
+    ack_ids: Arc<AckIdGenerator>,
     // namespace, for multiplexing messages
     nsp: String,
     // Data send in the opening packet (commonly used as for auth)
@@ -54,6 +54,7 @@ impl Client {
             on: Arc::new(RwLock::new(on)),
             on_any: Arc::new(RwLock::new(on_any)),
             outstanding_acks: Arc::new(RwLock::new(Vec::new())),
+            ack_ids: Arc::new(AckIdGenerator::new()),
             auth,
         })
     }
@@ -65,8 +66,7 @@ impl Client {
         self.socket.connect().await?;
 
         // construct the opening packet
-        let auth = self.auth.as_ref().map(|data| data.to_string());
-        let open_packet = Packet::new(PacketId::Connect, self.nsp.clone(), auth, None, 0, None);
+        let open_packet = Packet::connect(&self.nsp, self.auth.clone());
 
         self.socket.send(open_packet).await?;
 
@@ -148,8 +148,7 @@ impl Client {
     /// }
     /// ```
     pub async fn disconnect(&self) -> Result<()> {
-        let disconnect_packet =
-            Packet::new(PacketId::Disconnect, self.nsp.clone(), None, None, 0, None);
+        let disconnect_packet = Packet::disconnect(&self.nsp);
 
         self.socket.send(disconnect_packet).await?;
         self.socket.disconnect().await?;
@@ -218,7 +217,7 @@ impl Client {
         E: Into<Event>,
         D: Into<Payload>,
     {
-        let id = thread_rng().gen_range(0..999);
+        let id = self.ack_ids.next();
         let socket_packet =
             self.socket
                 .build_packet_for_payload(data.into(), event.into(), &self.nsp, Some(id))?;
@@ -339,12 +338,19 @@ impl Client {
                     _ => Event::Message,
                 };
 
-                (event, contents.get(1).ok_or(Error::IncompletePacket())?)
+                (
+                    event,
+                    contents
+                        .get(1)
+                        .ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))?,
+                )
             } else {
                 // case 2
                 (
                     Event::Message,
-                    contents.first().ok_or(Error::IncompletePacket())?,
+                    contents
+                        .first()
+                        .ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))?,
                 )
             };
 
@@ -448,6 +454,19 @@ mod test {
         Payload, TransportType,
     };
 
+    #[tokio::test]
+    async fn socket_io_invalid_namespace() {
+        // the namespace is checked before connecting
+        let result = ClientBuilder::new("http://localhost:4200")
+            .namespace("/ad,min")
+            .connect()
+            .await;
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidNamespace(nsp)) if nsp == "/ad,min"
+        ));
+    }
+
     #[tokio::test]
     async fn socket_io_integration() -> Result<()> {
         let url = crate::test::socket_io_server();
@@ -2,10 +2,10 @@ use super::generator::StreamGenerator;
 use crate::{
     error::Result,
     packet::{Packet, PacketId},
+    parser::Parser,
     Error, Event, Payload,
 };
 use async_stream::try_stream;
-use bytes::Bytes;
 use futures_util::{Stream, StreamExt};
 use rust_engineio::{
     asynchronous::Client as EngineClient, Packet as EnginePacket, PacketId as EnginePacketId,
@@ -25,17 +25,22 @@ use std::{
 pub(crate) struct Socket {
     engine_client: Arc<EngineClient>,
     connected: Arc<AtomicBool>,
+    parser: Arc<dyn Parser + Send + Sync>,
     generator: StreamGenerator<Packet>,
 }
 
 impl Socket {
     /// Creates an instance of `Socket`.
-    pub(super) fn new(engine_client: EngineClient) -> Result<Self> {
+    pub(super) fn new(
+        engine_client: EngineClient,
+        parser: Arc<dyn Parser + Send + Sync>,
+    ) -> Result<Self> {
         let connected = Arc::new(AtomicBool::default());
         Ok(Socket {
             engine_client: Arc::new(engine_client.clone()),
             connected: connected.clone(),
-            generator: StreamGenerator::new(Self::stream(engine_client, connected)),
+            parser: parser.clone(),
+            generator: StreamGenerator::new(Self::stream(engine_client, connected, parser)),
         })
     }
 
@@ -69,15 +74,17 @@ impl Socket {
             return Err(Error::IllegalActionBeforeOpen());
         }
 
-        // the packet, encoded as an engine.io message packet
-        let engine_packet = EnginePacket::new(EnginePacketId::Message, Bytes::from(&packet));
-        self.engine_client.emit(engine_packet).await?;
-
-        if let Some(attachments) = packet.attachments {
-            for attachment in attachments {
-                let engine_packet = EnginePacket::new(EnginePacketId::MessageBinary, attachment);
-                self.engine_client.emit(engine_packet).await?;
-            }
+        // the parser decides which frames are sent as engine.io message
+        // packets and which as binary ones
+        for (idx, frame) in self.parser.encode(&packet).into_iter().enumerate() {
+            let packet_id = if self.parser.is_binary_frame(idx) {
+                EnginePacketId::MessageBinary
+            } else {
+                EnginePacketId::Message
+            };
+            self.engine_client
+                .emit(EnginePacket::new(packet_id, frame))
+                .await?;
         }
 
         Ok(())
@@ -134,6 +141,7 @@ impl Socket {
     fn stream(
         client: EngineClient,
         is_connected: Arc<AtomicBool>,
+        parser: Arc<dyn Parser + Send + Sync>,
     ) -> Pin<Box<impl Stream<Item = Result<Packet>> + Send>> {
         Box::pin(try_stream! {
                 for await received_data in client.clone() {
@@ -142,7 +150,7 @@ impl Socket {
                     if packet.packet_id == EnginePacketId::Message
                         || packet.packet_id == EnginePacketId::MessageBinary
                     {
-                        let packet = Self::handle_engineio_packet(packet, client.clone()).await?;
+                        let packet = Self::handle_engineio_packet(packet, client.clone(), &*parser).await?;
                         Self::handle_socketio_packet(&packet, is_connected.clone());
 
                         yield packet;
@@ -172,35 +180,29 @@ impl Socket {
     async fn handle_engineio_packet(
         packet: EnginePacket,
         mut client: EngineClient,
+        parser: &(dyn Parser + Send + Sync),
     ) -> Result<Packet> {
-        let mut socket_packet = Packet::try_from(&packet.data)?;
+        let frame_count = parser.frame_count(&packet.data)?;
+        let mut frames = vec![packet.data];
 
         // Only handle attachments if there are any
-        if socket_packet.attachment_count > 0 {
-            let mut attachments_left = socket_packet.attachment_count;
-            let mut attachments = Vec::new();
-            while attachments_left > 0 {
-                // TODO: This is not nice! Find a different way to peek the next element while mapping the stream
-                let next = client.next().await.unwrap();
-                match next {
-                    Err(err) => return Err(err.into()),
-                    Ok(packet) => match packet.packet_id {
-                        EnginePacketId::MessageBinary | EnginePacketId::Message => {
-                            attachments.push(packet.data);
-                            attachments_left -= 1;
-                        }
-                        _ => {
-                            return Err(Error::InvalidAttachmentPacketType(
-                                packet.packet_id.into(),
-                            ));
-                        }
-                    },
-                }
+        while frames.len() < frame_count {
+            // TODO: This is not nice! Find a different way to peek the next element while mapping the stream
+            let next = client.next().await.unwrap();
+            match next {
+                Err(err) => return Err(err.into()),
+                Ok(packet) => match packet.packet_id {
+                    EnginePacketId::MessageBinary | EnginePacketId::Message => {
+                        frames.push(packet.data);
+                    }
+                    _ => {
+                        return Err(Error::InvalidAttachmentPacketType(packet.packet_id.into()));
+                    }
+                },
             }
-            socket_packet.attachments = Some(attachments);
         }
 
-        Ok(socket_packet)
+        parser.decode(&frames)
     }
 
     fn is_engineio_connected(&self) -> bool {
@@ -9,6 +9,8 @@ use url::Url;
 
 use crate::client::callback::{SocketAnyCallback, SocketCallback};
 use crate::error::Result;
+use crate::packet::Namespace;
+use crate::parser::{DefaultParser, Parser};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
@@ -41,6 +43,7 @@ pub struct ClientBuilder {
     opening_headers: Option<HeaderMap>,
     transport_type: TransportType,
     auth: Option<serde_json::Value>,
+    parser: Arc<dyn Parser + Send + Sync>,
     pub(crate) reconnect: bool,
     pub(crate) reconnect_on_disconnect: bool,
     // None reconnect attempts represent infinity.
@@ -90,6 +93,7 @@ impl ClientBuilder {
             opening_headers: None,
             transport_type: TransportType::Any,
             auth: None,
+            parser: Arc::new(DefaultParser),
             reconnect: true,
             reconnect_on_disconnect: false,
             // None means infinity
@@ -101,6 +105,8 @@ impl ClientBuilder {
 
     /// Sets the target namespace of the client. The namespace should start
     /// with a leading `/`. Valid examples are e.g. `/admin`, `/foo`.
+    /// A namespace that contains a `,` makes connecting fail with
+    /// [`crate::Error::InvalidNamespace`].
     pub fn namespace<T: Into<String>>(mut self, namespace: T) -> Self {
         let mut nsp = namespace.into();
         if !nsp.starts_with('/') {
@@ -302,6 +308,24 @@ impl ClientBuilder {
         self
     }
 
+    /// Specifies the [`Parser`] that packets are encoded and decoded with,
+    /// which has to match the parser the server uses. Defaults to
+    /// [`DefaultParser`].
+    /// # Example
+    /// ```rust
+    /// use rust_socketio::{ClientBuilder, parser::DefaultParser};
+    ///
+    /// let socket = ClientBuilder::new("http://localhost:4200/")
+    ///     .parser(DefaultParser)
+    ///     .connect()
+    ///     .expect("connection failed");
+    /// ```
+    pub fn parser<P: Parser + Send + Sync + 'static>(mut self, parser: P) -> Self {
+        self.parser = Arc::new(parser);
+
+        self
+    }
+
     /// Connects the socket to a certain endpoint. This returns a connected
     /// [`Client`] instance. This method returns an [`std::result::Result::Err`]
     /// value if something goes wrong during connection. Also starts a separate
@@ -330,6 +354,8 @@ impl ClientBuilder {
     }
 
     pub fn connect_raw(self) -> Result<RawClient> {
+        let namespace = Namespace::new(self.namespace)?;
+
         // Parse url here rather than in new to keep new returning Self.
         let mut url = Url::parse(&self.address)?;
 
@@ -353,15 +379,9 @@ impl ClientBuilder {
             TransportType::WebsocketUpgrade => builder.build_websocket_with_upgrade()?,
         };
 
-        let inner_socket = InnerSocket::new(engine_client)?;
+        let inner_socket = InnerSocket::new(engine_client, self.parser)?;
 
-        let socket = RawClient::new(
-            inner_socket,
-            &self.namespace,
-            self.on,
-            self.on_any,
-            self.auth,
-        )?;
+        let socket = RawClient::new(inner_socket, namespace, self.on, self.on_any, self.auth)?;
         socket.connect()?;
 
         Ok(socket)
@@ -1,12 +1,11 @@
 use super::callback::Callback;
-use crate::packet::{Packet, PacketId};
+use crate::packet::{AckIdGenerator, Packet, PacketId};
 use crate::Error;
 pub(crate) use crate::{event::Event, payload::Payload};
-use rand::{thread_rng, Rng};
 use serde_json::Value;
 
 use crate::client::callback::{SocketAnyCallback, SocketCallback};
-use crate::error::Result;
+use crate::error::{IncompleteReason, Result};
 use std::collections::HashMap;
 use std::ops::DerefMut;
 use std::sync::{Arc, Mutex};
@@ -37,6 +36,7 @@ pub struct RawClient {
     on: Arc<Mutex<HashMap<Event, Callback<SocketCallback>>>>,
     on_any: Arc<Mutex<Option<Callback<SocketAnyCallback>>>>,
     outstanding_acks: Arc<Mutex<Vec<Ack>>>,
+    ack_ids: Arc<AckIdGenerator>,
     // namespace, for multiplexing messages
     nsp: String,
     // Data send in the opening packet (commonly used as for auth)
@@ -61,6 +61,7 @@ impl RawClient {
             on,
             on_any,
             outstanding_acks: Arc::new(Mutex::new(Vec::new())),
+            ack_ids: Arc::new(AckIdGenerator::new()),
             auth,
         })
     }
@@ -72,10 +73,8 @@ impl RawClient {
         // Connect the underlying socket
         self.socket.connect()?;
 
-        let auth = self.auth.as_ref().map(|data| data.to_string());
-
         // construct the opening packet
-        let open_packet = Packet::new(PacketId::Connect, self.nsp.clone(), auth, None, 0, None);
+        let open_packet = Packet::connect(&self.nsp, self.auth.clone());
 
         self.socket.send(open_packet)?;
 
@@ -142,8 +141,7 @@ impl RawClient {
     ///
     /// ```
     pub fn disconnect(&self) -> Result<()> {
-        let disconnect_packet =
-            Packet::new(PacketId::Disconnect, self.nsp.clone(), None, None, 0, None);
+        let disconnect_packet = Packet::disconnect(&self.nsp);
 
         // TODO: logging
         let _ = self.socket.send(disconnect_packet);
@@ -201,7 +199,7 @@ impl RawClient {
         E: Into<Event>,
         D: Into<Payload>,
     {
-        let id = thread_rng().gen_range(0..999);
+        let id = self.ack_ids.next();
         let socket_packet =
             self.socket
                 .build_packet_for_payload(data.into(), event.into(), &self.nsp, Some(id))?;
@@ -338,12 +336,19 @@ impl RawClient {
                     _ => Event::Message,
                 };
 
-                (event, contents.get(1).ok_or(Error::IncompletePacket())?)
+                (
+                    event,
+                    contents
+                        .get(1)
+                        .ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))?,
+                )
             } else {
                 // case 2
                 (
                     Event::Message,
-                    contents.first().ok_or(Error::IncompletePacket())?,
+                    contents
+                        .first()
+                        .ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))?,
                 )
             };
 
@@ -426,6 +431,18 @@ mod test {
     use serde_json::json;
     use std::time::Duration;
 
+    #[test]
+    fn socket_io_invalid_namespace() {
+        // the namespace is checked before connecting
+        let result = ClientBuilder::new("http://localhost:4200")
+            .namespace("/ad,min")
+            .connect_raw();
+        assert!(matches!(
+            result,
+            Err(crate::Error::InvalidNamespace(nsp)) if nsp == "/ad,min"
+        ));
+    }
+
     #[test]
     fn socket_io_integration() -> Result<()> {
         let url = crate::test::socket_io_server();
@@ -1,13 +1,33 @@
 use base64::DecodeError;
 use serde_json::Error as JsonError;
+use std::fmt::{Display, Formatter, Result as FmtResult};
 use std::io::Error as IoError;
 use std::num::ParseIntError;
 use std::str::Utf8Error;
 use thiserror::Error;
 use url::ParseError as UrlParseError;
 
+pub use rust_engineio::error::ErrorKind;
+
 /// Enumeration of all possible errors in the `socket.io` context.
 /// TODO: 0.4.X Do not expose non-trivial internal errors. Convert error to string.
+///
+/// The enum is `#[non_exhaustive]`, new variants may be added with any
+/// release. Code outside of this crate therefore has to match it with a
+/// wildcard arm:
+///
+/// ```rust
+/// use rust_socketio::Error;
+///
+/// fn is_malformed(error: &Error) -> bool {
+///     match error {
+///         Error::IncompletePacket(_) | Error::InvalidPacket() => true,
+///         _ => false,
+///     }
+/// }
+///
+/// assert!(is_malformed(&Error::InvalidPacket()));
+/// ```
 #[derive(Error, Debug)]
 #[non_exhaustive]
 #[cfg_attr(tarpaulin, ignore)]
@@ -16,10 +36,18 @@ pub enum Error {
     // Negative verb-object
     #[error("Invalid packet id: {0}")]
     InvalidPacketId(char),
-    #[error("Error while parsing an incomplete packet")]
-    IncompletePacket(),
+    #[error("Error while parsing an incomplete packet: {0}")]
+    IncompletePacket(IncompleteReason),
     #[error("Got an invalid packet which did not follow the protocol format")]
     InvalidPacket(),
+    #[error("Invalid namespace: {0:?}")]
+    InvalidNamespace(String),
+    #[error("Invalid ack id: {0}")]
+    InvalidAckId(String),
+    #[error("Ack packet without an id")]
+    MissingAckId(),
+    #[error("Event name {0:?} is reserved")]
+    ReservedEventName(String),
     #[error("An error occurred while decoding the utf-8 text: {0}")]
     InvalidUtf8(#[from] Utf8Error),
     #[error("An error occurred while encoding/decoding base64: {0}")]
@@ -34,6 +62,10 @@ pub enum Error {
     IllegalActionBeforeOpen(),
     #[error("string is not json serializable: {0}")]
     InvalidJson(#[from] JsonError),
+    #[error("Got invalid json data `{1}`: {0}")]
+    InvalidJsonData(#[source] JsonError, String),
+    #[error("Expected a JSON array but got `{0}`")]
+    ExpectedJsonArray(String),
     #[error("A lock was poisoned")]
     InvalidPoisonedLock(),
     #[error("Got an IO-Error: {0}")]
@@ -44,10 +76,82 @@ pub enum Error {
     IncompleteResponseFromEngineIo(#[from] rust_engineio::Error),
     #[error("Invalid packet type while reading attachments")]
     InvalidAttachmentPacketType(u8),
+    #[error("Placeholder refers to attachment {0}, which is out of range")]
+    AttachmentIndexOutOfRange(u64),
+    #[error("No placeholder refers to attachment {0}")]
+    MissingAttachmentIndex(u8),
+    #[error("Attachment {0} is referred to by more than one placeholder")]
+    DuplicateAttachmentIndex(u64),
+    #[error("Received a packet header while {0} attachments were still missing")]
+    UnexpectedHeaderDuringReassembly(usize),
+    #[error("Packet announces {0} attachments, more than allowed")]
+    TooManyAttachments(u64),
     #[error("Underlying Engine.IO connection has closed")]
     StoppedEngineIoSocket,
 }
 
+/// Describes where the input of an [`Error::IncompletePacket`] ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum IncompleteReason {
+    /// There was no input at all.
+    EmptyInput,
+    /// The namespace isn't followed by a `,` although data follows it.
+    MissingNamespaceComma,
+    /// The attachment count of a binary packet isn't followed by a `-`.
+    MissingAttachmentDash,
+    /// The input ended in the middle of a packet, or before all of its
+    /// parts arrived.
+    UnexpectedEnd,
+}
+
+impl Display for IncompleteReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            IncompleteReason::EmptyInput => "empty input",
+            IncompleteReason::MissingNamespaceComma => "missing ',' after the namespace",
+            IncompleteReason::MissingAttachmentDash => "missing '-' after the attachment count",
+            IncompleteReason::UnexpectedEnd => "unexpected end of input",
+        })
+    }
+}
+
+impl Error {
+    /// Returns the broad category of this error. Errors of the underlying
+    /// `engine.io` connection keep their own category.
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::IncompletePacket(_) => ErrorKind::Incomplete,
+            Error::InvalidPacketId(_)
+            | Error::InvalidPacket()
+            | Error::InvalidNamespace(_)
+            | Error::InvalidAckId(_)
+            | Error::MissingAckId()
+            | Error::InvalidUtf8(_)
+            | Error::InvalidBase64(_)
+            | Error::InvalidUrl(_)
+            | Error::InvalidHandshake(_)
+            | Error::InvalidJson(_)
+            | Error::InvalidJsonData(..)
+            | Error::ExpectedJsonArray(_)
+            | Error::InvalidInteger(_)
+            | Error::InvalidAttachmentPacketType(_)
+            | Error::AttachmentIndexOutOfRange(_)
+            | Error::MissingAttachmentIndex(_)
+            | Error::DuplicateAttachmentIndex(_)
+            | Error::UnexpectedHeaderDuringReassembly(_) => ErrorKind::Malformed,
+            Error::ReservedEventName(_)
+            | Error::TooManyAttachments(_)
+            | Error::InvalidUrlScheme(_)
+            | Error::IllegalActionBeforeOpen() => ErrorKind::Unsupported,
+            Error::IncompleteResponseFromEngineIo(err) => err.kind(),
+            Error::InvalidPoisonedLock()
+            | Error::IncompleteIo(_)
+            | Error::StoppedEngineIoSocket => ErrorKind::Io,
+        }
+    }
+}
+
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
 impl<T> From<std::sync::PoisonError<T>> for Error {
@@ -75,8 +179,35 @@ mod tests {
         let _error = Error::from(PoisonError::new(mutex.lock()));
         assert!(matches!(Error::InvalidPoisonedLock(), _error));
 
-        let _io_error = std::io::Error::from(Error::IncompletePacket());
-        let _error = std::io::Error::new(std::io::ErrorKind::Other, Error::IncompletePacket());
+        let _io_error = std::io::Error::from(Error::IncompletePacket(IncompleteReason::EmptyInput));
+        let _error = std::io::Error::new(
+            std::io::ErrorKind::Other,
+            Error::IncompletePacket(IncompleteReason::EmptyInput),
+        );
         assert!(matches!(_io_error, _error));
     }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(
+            Error::IncompletePacket(IncompleteReason::UnexpectedEnd).kind(),
+            ErrorKind::Incomplete
+        );
+        assert_eq!(
+            Error::MissingAttachmentIndex(0).kind(),
+            ErrorKind::Malformed
+        );
+        assert_eq!(
+            Error::ReservedEventName("connect".to_owned()).kind(),
+            ErrorKind::Unsupported
+        );
+        assert_eq!(Error::StoppedEngineIoSocket.kind(), ErrorKind::Io);
+        assert_eq!(
+            Error::from(rust_engineio::Error::IncompletePacket(
+                rust_engineio::error::IncompleteReason::EmptyInput
+            ))
+            .kind(),
+            ErrorKind::Incomplete
+        );
+    }
 }
@@ -164,7 +164,13 @@ pub mod client;
 /// Deprecated import since 0.3.0-alpha-2, use Event in the crate root instead.
 /// Defines the events that could be sent or received.
 pub mod event;
-pub(crate) mod packet;
+/// Defines the packets of the `socket.io` protocol and their wire format.
+/// The module is public so that packets can be built, inspected and
+/// encoded without a client, e.g. together with the [`crate::parser`]s.
+pub mod packet;
+/// Defines the parsers that convert packets from and to the frames exchanged
+/// via the `engine.io` connection.
+pub mod parser;
 /// Deprecated import since 0.3.0-alpha-2, use Event in the crate root instead.
 /// Defines the types of payload (binary or string), that
 /// could be sent or received.
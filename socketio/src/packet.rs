@@ -1,13 +1,20 @@
-use crate::error::{Error, Result};
-use bytes::Bytes;
-use serde::de::IgnoredAny;
-
+use crate::error::{Error, IncompleteReason, Result};
+use bytes::{BufMut, Bytes, BytesMut};
+use rust_engineio::{Packet as EnginePacket, PacketId as EnginePacketId};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::Serialize;
+use serde_json::Value;
+
+use std::borrow::Cow;
 use std::convert::TryFrom;
-use std::fmt::Write;
+use std::fmt::{Display, Formatter, Result as FmtResult};
+use std::io::Read;
+use std::ops::Deref;
 use std::str::from_utf8 as str_from_utf8;
+use std::sync::atomic::{AtomicI32, Ordering};
 
 /// An enumeration of the different `Packet` types in the `socket.io` protocol.
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub enum PacketId {
     Connect = 0,
     Disconnect = 1,
@@ -29,6 +36,74 @@ pub struct Packet {
     pub attachments: Option<Vec<Bytes>>,
 }
 
+/// A validated `socket.io` namespace. It always starts with a `/` and
+/// never contains a `,`, which terminates the namespace on the wire.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Namespace(String);
+
+impl Namespace {
+    /// Creates a namespace, failing with [`Error::InvalidNamespace`] if it
+    /// doesn't start with a `/` or contains a `,`.
+    pub fn new<T: Into<String>>(namespace: T) -> Result<Self> {
+        let namespace = namespace.into();
+        if !namespace.starts_with('/') || namespace.contains(',') {
+            return Err(Error::InvalidNamespace(namespace));
+        }
+
+        Ok(Namespace(namespace))
+    }
+}
+
+impl Default for Namespace {
+    fn default() -> Self {
+        Namespace(String::from("/"))
+    }
+}
+
+impl Deref for Namespace {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Namespace {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<Namespace> for String {
+    fn from(namespace: Namespace) -> Self {
+        namespace.0
+    }
+}
+
+/// Hands out ascending ids for packets that expect an acknowledgement, so
+/// that the server's `Ack` can be matched by its id. The ids never get
+/// negative, after `i32::MAX` they start over at 0.
+#[derive(Debug, Default)]
+pub struct AckIdGenerator {
+    next: AtomicI32,
+}
+
+impl AckIdGenerator {
+    /// Creates a generator whose first id is 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the next id.
+    pub fn next(&self) -> i32 {
+        self.next
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |id| {
+                Some(id.checked_add(1).unwrap_or(0))
+            })
+            .unwrap_or_default()
+    }
+}
+
 impl Default for Packet {
     fn default() -> Self {
         Self {
@@ -44,14 +119,55 @@ impl Default for Packet {
 
 impl TryFrom<u8> for PacketId {
     type Error = Error;
+    /// Converts a byte into the corresponding `PacketId`, accepting both the
+    /// raw value and its ASCII digit like the `engine.io` counterpart.
     fn try_from(b: u8) -> Result<Self> {
-        PacketId::try_from(b as char)
+        match b {
+            0..=6 => PacketId::from_digit(char::from(b + b'0')),
+            _ => PacketId::from_digit(char::from(b)),
+        }
     }
 }
 
-impl TryFrom<char> for PacketId {
-    type Error = Error;
-    fn try_from(b: char) -> Result<Self> {
+impl PacketId {
+    /// Returns every [`PacketId`], ordered by their value.
+    pub fn all() -> &'static [PacketId] {
+        &[
+            PacketId::Connect,
+            PacketId::Disconnect,
+            PacketId::Event,
+            PacketId::Ack,
+            PacketId::ConnectError,
+            PacketId::BinaryEvent,
+            PacketId::BinaryAck,
+        ]
+    }
+
+    /// Returns whether packets of this type manage the connection to a
+    /// namespace, i.e. `Connect`, `Disconnect` and `ConnectError`.
+    pub fn is_control(self) -> bool {
+        matches!(
+            self,
+            PacketId::Connect | PacketId::Disconnect | PacketId::ConnectError
+        )
+    }
+
+    /// Returns whether packets of this type carry events or acks, including
+    /// their binary variants.
+    pub fn is_data(self) -> bool {
+        !self.is_control()
+    }
+
+    /// Returns whether packets of this type answer an event and therefore
+    /// have to carry the id of that event.
+    pub fn requires_ack_id(self) -> bool {
+        matches!(self, PacketId::Ack | PacketId::BinaryAck)
+    }
+
+    /// Parses the digit that starts every encoded packet. Any other
+    /// character, including multibyte ones, is returned in the error as a
+    /// whole.
+    pub fn from_digit(b: char) -> Result<Self> {
         match b {
             '0' => Ok(PacketId::Connect),
             '1' => Ok(PacketId::Disconnect),
@@ -65,7 +181,18 @@ impl TryFrom<char> for PacketId {
     }
 }
 
+impl TryFrom<char> for PacketId {
+    type Error = Error;
+    fn try_from(b: char) -> Result<Self> {
+        PacketId::from_digit(b)
+    }
+}
+
 impl Packet {
+    /// The number of attachments a packet may announce when it is decoded.
+    /// Headers that announce more fail with [`Error::TooManyAttachments`].
+    pub const DEFAULT_MAX_ATTACHMENTS: u8 = 64;
+
     /// Creates an instance.
     pub const fn new(
         packet_type: PacketId,
@@ -84,198 +211,1091 @@ impl Packet {
             attachments,
         }
     }
-}
 
-impl From<Packet> for Bytes {
-    fn from(packet: Packet) -> Self {
-        Bytes::from(&packet)
+    /// Creates a `BinaryEvent` for the given event whose attachments are
+    /// collected from an iterator. Fails if there are more attachments than
+    /// fit into the attachment count.
+    pub fn binary_event_from_iter<I: IntoIterator<Item = Bytes>>(
+        event: &str,
+        nsp: &str,
+        attachments: I,
+    ) -> Result<Self> {
+        let attachments: Vec<Bytes> = attachments.into_iter().collect();
+        let attachment_count =
+            u8::try_from(attachments.len()).map_err(|_| Error::InvalidPacket())?;
+
+        Ok(Packet::new(
+            PacketId::BinaryEvent,
+            nsp.to_owned(),
+            Some(Value::String(event.to_owned()).to_string()),
+            None,
+            attachment_count,
+            Some(attachments),
+        ))
     }
-}
 
-impl From<&Packet> for Bytes {
-    /// Method for encoding from a `Packet` to a `u8` byte stream.
-    /// The binary payload of a packet is not put at the end of the
-    /// stream as it gets handled and send by it's own logic via the socket.
-    fn from(packet: &Packet) -> Bytes {
-        // first the packet type
-        let mut buffer = String::new();
-        buffer.push((packet.packet_type as u8 + b'0') as char);
+    /// Creates a packet from the parts returned by [`Packet::into_parts`].
+    /// The attachment count is taken from the number of attachments, which
+    /// must not exceed 255.
+    pub fn from_parts(
+        packet_type: PacketId,
+        nsp: String,
+        data: Option<String>,
+        id: Option<i32>,
+        attachments: Option<Vec<Bytes>>,
+    ) -> Result<Self> {
+        let attachment_count = attachments.as_ref().map_or(Ok(0), |attachments| {
+            u8::try_from(attachments.len()).map_err(|_| Error::InvalidPacket())
+        })?;
 
-        // eventually a number of attachments, followed by '-'
-        if let PacketId::BinaryAck | PacketId::BinaryEvent = packet.packet_type {
-            let _ = write!(buffer, "{}-", packet.attachment_count);
+        Ok(Packet::new(
+            packet_type,
+            nsp,
+            data,
+            id,
+            attachment_count,
+            attachments,
+        ))
+    }
+
+    /// Splits the packet into its type, namespace, data, id and attachments,
+    /// moving them out without copying.
+    pub fn into_parts(
+        self,
+    ) -> (
+        PacketId,
+        String,
+        Option<String>,
+        Option<i32>,
+        Option<Vec<Bytes>>,
+    ) {
+        (
+            self.packet_type,
+            self.nsp,
+            self.data,
+            self.id,
+            self.attachments,
+        )
+    }
+
+    /// Creates a `Connect` packet for `nsp`, which carries the serialized
+    /// `auth` data if there is any.
+    pub fn connect(nsp: &str, auth: Option<Value>) -> Packet {
+        let data = auth.map(|auth| auth.to_string());
+        Packet::new(PacketId::Connect, nsp.to_owned(), data, None, 0, None)
+    }
+
+    /// Creates a `Disconnect` packet for `nsp`.
+    pub fn disconnect(nsp: &str) -> Packet {
+        Packet::new(PacketId::Disconnect, nsp.to_owned(), None, None, 0, None)
+    }
+
+    /// Creates an `Event` packet for `event` whose arguments are serialized
+    /// from `args`. A tuple or any other value serializing to a JSON array
+    /// is spread into multiple arguments, `()` results in no arguments and
+    /// any other value is sent as the only argument.
+    pub fn from_event<T: Serialize>(nsp: &str, event: &str, args: T) -> Result<Packet> {
+        let mut data = vec![Value::String(event.to_owned())];
+        data.extend(serialize_args(args)?);
+
+        Ok(Packet::new(
+            PacketId::Event,
+            nsp.to_owned(),
+            Some(Value::Array(data).to_string()),
+            None,
+            0,
+            None,
+        ))
+    }
+
+    /// Creates an `Ack` packet that answers the event with the given `id`.
+    /// The response `args` are serialized the same way as the arguments of
+    /// [`Packet::from_event`].
+    pub fn ack<T: Serialize>(nsp: &str, id: i32, args: T) -> Result<Packet> {
+        let data = Value::Array(serialize_args(args)?);
+
+        Ok(Packet::new(
+            PacketId::Ack,
+            nsp.to_owned(),
+            Some(data.to_string()),
+            Some(id),
+            0,
+            None,
+        ))
+    }
+
+    /// Sets the id the server acknowledges this packet with.
+    pub fn with_ack_id(mut self, id: i32) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Turns the packet into a `Connect` packet that carries `auth` as its
+    /// data, as sent to a namespace with version 5 of the protocol.
+    pub fn with_connect_auth<T: Serialize>(mut self, auth: &T) -> Result<Self> {
+        self.packet_type = PacketId::Connect;
+        self.data = Some(serde_json::to_string(auth)?);
+        Ok(self)
+    }
+
+    /// Wraps the encoded packet into an `engine.io` message, which is how it
+    /// is transmitted. Attachments are not part of the message, they are
+    /// sent as separate `MessageBinary` packets afterwards.
+    pub fn to_engineio_message(&self) -> EnginePacket {
+        EnginePacket::new(EnginePacketId::Message, Bytes::from(self))
+    }
+
+    /// Decodes the packet carried by an `engine.io` message. Any other
+    /// `engine.io` packet fails with `UnexpectedPacketType`.
+    pub fn from_engine_message(message: &EnginePacket) -> Result<Packet> {
+        if message.packet_id != EnginePacketId::Message {
+            return Err(rust_engineio::Error::UnexpectedPacketType(message.packet_id).into());
         }
+        Packet::try_from(&message.data)
+    }
 
-        // if the namespace is different from the default one append it as well,
-        // followed by ','
-        if packet.nsp != "/" {
-            buffer.push_str(&packet.nsp);
-            buffer.push(',');
+    /// Clones the packet while sharing the buffers of its attachments with
+    /// the original, as `Bytes` are reference counted. Only the namespace,
+    /// the data and the list of attachments are copied, never the binary
+    /// data itself.
+    pub fn clone_shallow(&self) -> Packet {
+        Packet {
+            nsp: self.nsp.clone(),
+            data: self.data.clone(),
+            attachments: self
+                .attachments
+                .as_ref()
+                .map(|attachments| attachments.iter().map(Bytes::clone).collect()),
+            ..*self
         }
+    }
 
-        // if an id is present append it...
-        if let Some(id) = packet.id {
-            let _ = write!(buffer, "{id}");
+    /// Clones the packet onto the namespace `nsp`, e.g. to re-emit an event
+    /// on another namespace. The data, the id and the attachments are kept.
+    pub fn clone_with_namespace<T: Into<String>>(&self, nsp: T) -> Packet {
+        Packet {
+            nsp: nsp.into(),
+            data: self.data.clone(),
+            attachments: self.attachments.clone(),
+            ..*self
         }
+    }
 
-        if packet.attachments.is_some() {
-            let num = packet.attachment_count - 1;
+    /// Returns the attachment at `index`, or `None` if there is no such
+    /// attachment.
+    pub fn attachment(&self, index: usize) -> Option<&Bytes> {
+        self.attachments.as_ref()?.get(index)
+    }
 
-            // check if an event type is present
-            if let Some(event_type) = packet.data.as_ref() {
-                let _ = write!(
-                    buffer,
-                    "[{event_type},{{\"_placeholder\":true,\"num\":{num}}}]",
-                );
+    /// Concatenates all attachments into a single one, for transports that
+    /// prefer one binary blob. Placeholders referring to the other
+    /// attachments are removed from the data, so that only the one with
+    /// `num` 0 remains once the packet is encoded.
+    ///
+    /// This is lossy: the receiver gets a single attachment and can no
+    /// longer tell where one of the original attachments ended.
+    pub fn coalesce_attachments(&mut self) {
+        let Some(attachments) = self.attachments.as_mut() else {
+            return;
+        };
+        if attachments.len() < 2 {
+            return;
+        }
+
+        let mut buffer = BytesMut::with_capacity(attachments.iter().map(Bytes::len).sum());
+        for attachment in attachments.iter() {
+            buffer.extend_from_slice(attachment);
+        }
+        *attachments = vec![buffer.freeze()];
+        self.attachment_count = 1;
+
+        // decoded data is already free of placeholders, only ones that were
+        // left in by hand are removed while the user data is kept verbatim.
+        // Invalid data is left alone, stripping would cut it off.
+        let Some(data) = self.data.as_deref() else {
+            return;
+        };
+        if serde_json::from_str::<IgnoredAny>(&format!("[{data}]")).is_ok() {
+            let data = strip_placeholders(data).into_owned();
+            self.data = (!data.is_empty()).then_some(data);
+        }
+    }
+
+    /// Resets the packet to an `Event` without data on the default
+    /// namespace. The allocation of the namespace is kept.
+    pub fn reset(&mut self) {
+        self.packet_type = PacketId::Event;
+        self.nsp.clear();
+        self.nsp.push('/');
+        self.data = None;
+        self.id = None;
+        self.attachment_count = 0;
+        self.attachments = None;
+    }
+
+    /// Decodes `payload` into this packet like `Packet::try_from`, but
+    /// reuses the allocations of the namespace and the data, which pays off
+    /// when decoding many packets in a loop. If decoding fails, the packet
+    /// is left partially decoded. At most
+    /// [`Packet::DEFAULT_MAX_ATTACHMENTS`] attachments may be announced.
+    pub fn decode_into(&mut self, payload: &[u8]) -> Result<()> {
+        self.decode_into_limited(payload, Packet::DEFAULT_MAX_ATTACHMENTS)
+    }
+
+    /// Decodes `payload` like [`Packet::decode_into`], allowing at most
+    /// `max_attachments` attachments.
+    pub(crate) fn decode_into_limited(
+        &mut self,
+        payload: &[u8],
+        max_attachments: u8,
+    ) -> Result<()> {
+        self.decode_into_with(payload, max_attachments, false)
+    }
+
+    /// Decodes `payload` into this packet. In `strict` mode only the exact
+    /// format the encoder writes is accepted, see [`Packet::try_from_strict`].
+    fn decode_into_with(
+        &mut self,
+        payload: &[u8],
+        max_attachments: u8,
+        strict: bool,
+    ) -> Result<()> {
+        let payload = str_from_utf8(payload).map_err(Error::InvalidUtf8)?;
+        let mut data = self.data.take().unwrap_or_default();
+        self.reset();
+
+        let payload = self.decode_header_into(payload, max_attachments, strict)?;
+        let is_binary = matches!(
+            self.packet_type,
+            PacketId::BinaryAck | PacketId::BinaryEvent
+        );
+
+        // binary packets always carry at least their placeholders
+        if payload.is_empty() {
+            return if is_binary {
+                Err(Error::InvalidPacket())
             } else {
-                let _ = write!(buffer, "[{{\"_placeholder\":true,\"num\":{num}}}]");
+                Ok(())
+            };
+        }
+
+        // validate json
+        serde_json::from_str::<IgnoredAny>(payload)
+            .map_err(|err| Error::InvalidJsonData(err, json_snippet(payload)))?;
+
+        // events and acks carry their arguments in an array, the json is
+        // valid so it's enough to look at the first character
+        let carries_args = matches!(
+            self.packet_type,
+            PacketId::Event | PacketId::Ack | PacketId::BinaryEvent | PacketId::BinaryAck
+        );
+        if carries_args && !payload.trim_start().starts_with('[') {
+            return Err(Error::ExpectedJsonArray(json_snippet(payload)));
+        }
+
+        let body = payload;
+        let payload = if is_binary {
+            let payload = payload
+                .trim()
+                .strip_prefix('[')
+                .and_then(|payload| payload.strip_suffix(']'))
+                .ok_or(Error::InvalidPacket())?;
+            validate_placeholders(payload, self.attachment_count, strict)?;
+
+            let stripped = strip_placeholders(payload);
+            // a borrowed result is a prefix that ends with a complete value,
+            // values that were stitched together have to be checked again
+            if let Cow::Owned(stripped) = &stripped {
+                serde_json::from_str::<IgnoredAny>(&format!("[{stripped}]"))
+                    .map_err(|err| Error::InvalidJsonData(err, json_snippet(body)))?;
             }
-        } else if let Some(data) = packet.data.as_ref() {
-            buffer.push_str(data);
+            stripped
+        } else {
+            Cow::Borrowed(payload)
+        };
+
+        if !payload.is_empty() {
+            data.clear();
+            data.push_str(&payload);
+            self.data = Some(data);
         }
 
-        Bytes::from(buffer)
+        Ok(())
     }
-}
 
-impl TryFrom<Bytes> for Packet {
-    type Error = Error;
-    fn try_from(value: Bytes) -> Result<Self> {
-        Packet::try_from(&value)
+    /// Decodes `payload` like `Packet::try_from`, but only accepts the exact
+    /// format the encoder writes. A namespace has to be followed by a `,`
+    /// even if nothing comes after it, and placeholders have to be written
+    /// as `{"_placeholder":true,"num":<n>}`. The packet is then checked with
+    /// [`Packet::validate`], so that e.g. an ack without an id is rejected.
+    pub fn try_from_strict(payload: &Bytes) -> Result<Packet> {
+        let mut packet = Packet::default();
+        packet.decode_into_with(payload, Packet::DEFAULT_MAX_ATTACHMENTS, true)?;
+        packet.validate()?;
+        Ok(packet)
     }
-}
 
-impl TryFrom<&Bytes> for Packet {
-    type Error = Error;
-    /// Decodes a packet given a `Bytes` type.
-    /// The binary payload of a packet is not put at the end of the
-    /// stream as it gets handled and send by it's own logic via the socket.
-    /// Therefore this method does not return the correct value for the
-    /// binary data, instead the socket is responsible for handling
-    /// this member. This is done because the attachment is usually
-    /// send in another packet.
-    fn try_from(payload: &Bytes) -> Result<Packet> {
-        let mut payload = str_from_utf8(&payload).map_err(Error::InvalidUtf8)?;
+    /// Reads `reader` to its end and decodes the bytes as a single packet.
+    /// Errors of the reader are returned as `IncompleteIo`.
+    pub fn try_from_reader<R: Read>(reader: &mut R) -> Result<Packet> {
+        let mut payload = Vec::new();
+        reader.read_to_end(&mut payload)?;
+        Packet::try_from(payload.as_slice())
+    }
+
+    /// Decodes only the type, attachment count, namespace and id of a packet.
+    /// The body is neither validated nor stored, so `data` is always `None`.
+    pub fn decode_header_only(payload: &Bytes) -> Result<Packet> {
+        let payload = str_from_utf8(payload).map_err(Error::InvalidUtf8)?;
+        Ok(Packet::decode_header(payload)?.0)
+    }
+
+    /// Decodes the header of a packet and returns it together with the
+    /// remaining, undecoded body.
+    fn decode_header(payload: &str) -> Result<(Packet, &str)> {
         let mut packet = Packet::default();
+        let payload = packet.decode_header_into(payload, Packet::DEFAULT_MAX_ATTACHMENTS, false)?;
+        Ok((packet, payload))
+    }
 
+    /// Decodes the header of a packet into this packet, which has to be
+    /// reset, and returns the remaining, undecoded body. Headers that announce
+    /// more than `max_attachments` attachments are rejected. In `strict` mode
+    /// the namespace has to be terminated by a `,`.
+    fn decode_header_into<'a>(
+        &mut self,
+        mut payload: &'a str,
+        max_attachments: u8,
+        strict: bool,
+    ) -> Result<&'a str> {
         // packet_type
-        let id_char = payload.chars().next().ok_or(Error::IncompletePacket())?;
-        packet.packet_type = PacketId::try_from(id_char)?;
+        let id_char = payload
+            .chars()
+            .next()
+            .ok_or(Error::IncompletePacket(IncompleteReason::EmptyInput))?;
+        self.packet_type = PacketId::from_digit(id_char)?;
         payload = &payload[id_char.len_utf8()..];
 
         // attachment_count
-        if let PacketId::BinaryAck | PacketId::BinaryEvent = packet.packet_type {
-            let (prefix, rest) = payload.split_once('-').ok_or(Error::IncompletePacket())?;
+        if let PacketId::BinaryAck | PacketId::BinaryEvent = self.packet_type {
+            let (prefix, rest) = payload.split_once('-').ok_or(Error::IncompletePacket(
+                IncompleteReason::MissingAttachmentDash,
+            ))?;
             payload = rest;
-            packet.attachment_count = prefix.parse().map_err(|_| Error::InvalidPacket())?;
+            let attachment_count: u64 = prefix.parse().map_err(|_| Error::InvalidPacket())?;
+            if attachment_count > u64::from(max_attachments) {
+                return Err(Error::TooManyAttachments(attachment_count));
+            }
+            self.attachment_count = attachment_count as u8;
         }
 
-        // namespace
+        // namespace, some implementations leave out the terminating ',' if
+        // nothing follows it
         if payload.starts_with('/') {
-            let (prefix, rest) = payload.split_once(',').ok_or(Error::IncompletePacket())?;
+            let (prefix, rest) = match payload.split_once(',') {
+                Some(split) => split,
+                None if strict || payload.contains(['[', '{', '"']) => {
+                    return Err(Error::IncompletePacket(
+                        IncompleteReason::MissingNamespaceComma,
+                    ))
+                }
+                None => (payload, ""),
+            };
             payload = rest;
-            packet.nsp.clear(); // clearing the default
-            packet.nsp.push_str(prefix);
+            self.nsp.clear(); // clearing the default
+            self.nsp.push_str(prefix);
         }
 
-        // id
-        let Some((non_digit_idx, _)) = payload.char_indices().find(|(_, c)| !c.is_ascii_digit()) else {
-            return Ok(packet);
-        };
+        // id, which is never signed. A sign in front of digits is rejected
+        // instead of taking it for the start of the data and dropping the id
+        if let Some(unsigned) = payload.strip_prefix(|c: char| c == '+' || c == '-') {
+            if unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+                let digits = unsigned
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(unsigned.len());
+                return Err(Error::InvalidAckId(payload[..digits + 1].to_owned()));
+            }
+        }
+
+        // the id may be the last part of the packet if no data follows
+        let non_digit_idx = payload
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(payload.len());
 
         if non_digit_idx > 0 {
             let (prefix, rest) = payload.split_at(non_digit_idx);
             payload = rest;
-            packet.id = Some(prefix.parse().map_err(|_| Error::InvalidPacket())?);
+            self.id = Some(prefix.parse().map_err(|_| Error::InvalidPacket())?);
         }
 
-        // validate json
-        serde_json::from_str::<IgnoredAny>(payload).map_err(Error::InvalidJson)?;
+        Ok(payload)
+    }
 
-        match packet.packet_type {
-            PacketId::BinaryAck | PacketId::BinaryEvent => {
-                if payload.starts_with('[') && payload.ends_with(']') {
-                    payload = &payload[1..payload.len() - 1];
-                }
+    /// Returns a lazy iterator over the arguments of an event or an ack,
+    /// without the event name. Every argument is only parsed once the
+    /// iterator reaches it. An argument that is not valid JSON is yielded
+    /// as [`Error::InvalidJsonData`], after which the iterator ends.
+    pub fn iter_args(&self) -> Result<impl Iterator<Item = Result<Value>> + '_> {
+        let data = self.data.as_deref().unwrap_or_default().trim();
+
+        // binary packets store their data without the surrounding brackets
+        let args = match self.packet_type {
+            PacketId::Event | PacketId::Ack => data
+                .strip_prefix('[')
+                .and_then(|data| data.strip_suffix(']'))
+                .ok_or(Error::InvalidPacket())?,
+            PacketId::BinaryEvent | PacketId::BinaryAck => data,
+            _ => return Err(Error::InvalidPacket()),
+        };
 
-                let mut str = payload.replace("{\"_placeholder\":true,\"num\":0}", "");
+        let mut args = Args { rest: args };
+        if let PacketId::Event | PacketId::BinaryEvent = self.packet_type {
+            // skip the event name
+            args.next().transpose()?;
+        }
 
-                if str.ends_with(',') {
-                    str.pop();
-                }
+        Ok(args)
+    }
 
-                if !str.is_empty() {
-                    packet.data = Some(str);
-                }
-            }
-            _ => packet.data = Some(payload.to_string()),
-        }
+    /// Rewrites the data of the packet by parsing it as JSON, applying `f` and
+    /// serializing the result again. A packet without data is passed as
+    /// `Value::Null`, and a `Value::Null` result removes the data. The data
+    /// of binary packets is passed as an array, like for any other event.
+    pub fn map_data<F: FnOnce(Value) -> Value>(&mut self, f: F) -> Result<()> {
+        let is_binary = matches!(
+            self.packet_type,
+            PacketId::BinaryEvent | PacketId::BinaryAck
+        );
 
-        Ok(packet)
+        // binary packets store their data without the surrounding brackets
+        let value = match self.data.as_deref() {
+            Some(data) if is_binary => serde_json::from_str(&format!("[{data}]"))?,
+            Some(data) => serde_json::from_str(data)?,
+            None => Value::Null,
+        };
+
+        self.data = match f(value) {
+            Value::Null => None,
+            Value::Array(values) if is_binary => join_binary_args(&values),
+            value => Some(value.to_string()),
+        };
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
+    /// Returns the id of the packet that is acknowledged by this `Ack` or
+    /// `BinaryAck`, and `None` for any other packet.
+    pub fn ack_id(&self) -> Option<i32> {
+        match self.packet_type {
+            PacketId::Ack | PacketId::BinaryAck => self.id,
+            _ => None,
+        }
+    }
 
-    #[test]
-    /// This test suite is taken from the explanation section here:
-    /// https://github.com/socketio/socket.io-protocol
-    fn test_decode() {
-        let payload = Bytes::from_static(b"0{\"token\":\"123\"}");
-        let packet = Packet::try_from(&payload);
-        assert!(packet.is_ok());
+    /// Deserializes the arguments of an `Ack` or `BinaryAck` as a whole,
+    /// e.g. into a tuple or a `Vec`. Placeholders of attachments are not
+    /// part of the arguments.
+    pub fn ack_args<T: DeserializeOwned>(&self) -> Result<T> {
+        let data = self.data.as_deref();
+
+        // binary packets store their data without the surrounding brackets
+        Ok(match self.packet_type {
+            PacketId::Ack => serde_json::from_str(data.unwrap_or("[]"))?,
+            PacketId::BinaryAck => {
+                serde_json::from_str(&format!("[{}]", data.unwrap_or_default()))?
+            }
+            _ => return Err(Error::InvalidPacket()),
+        })
+    }
 
-        assert_eq!(
-            Packet::new(
-                PacketId::Connect,
-                "/".to_owned(),
-                Some(String::from("{\"token\":\"123\"}")),
-                None,
-                0,
-                None,
-            ),
-            packet.unwrap()
-        );
+    /// Returns the reason of a `ConnectError`, which is either sent as the
+    /// `message` of an object or as a bare string. Returns `None` for any
+    /// other packet or if no reason can be found.
+    pub fn connect_error_message(&self) -> Option<String> {
+        if self.packet_type != PacketId::ConnectError {
+            return None;
+        }
 
-        let utf8_data = "{\"token™\":\"123\"}".to_owned();
-        let utf8_payload = format!("0/admin™,{}", utf8_data);
-        let payload = Bytes::from(utf8_payload);
-        let packet = Packet::try_from(&payload);
-        assert!(packet.is_ok());
+        match serde_json::from_str(self.data.as_deref()?).ok()? {
+            Value::String(message) => Some(message),
+            Value::Object(mut map) => match map.remove("message")? {
+                Value::String(message) => Some(message),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
 
-        assert_eq!(
-            Packet::new(
-                PacketId::Connect,
-                "/admin™".to_owned(),
-                Some(utf8_data),
-                None,
-                0,
-                None,
-            ),
-            packet.unwrap()
-        );
+    /// Checks the packet against the rules of the protocol. Acks have to
+    /// carry an id and the data of an `Ack` has to be a JSON array. This is
+    /// mostly useful for packets that are built by hand, decoding already
+    /// enforces the array.
+    pub fn validate(&self) -> Result<()> {
+        if self.packet_type.requires_ack_id() && self.id.is_none() {
+            return Err(Error::MissingAckId());
+        }
 
-        let payload = Bytes::from_static(b"1/admin,");
-        let packet = Packet::try_from(&payload);
-        assert!(packet.is_ok());
+        if self.packet_type == PacketId::Ack {
+            let data = self.data.as_deref().unwrap_or_default();
+            if !matches!(serde_json::from_str(data), Ok(Value::Array(_))) {
+                return Err(Error::ExpectedJsonArray(json_snippet(data)));
+            }
+        }
 
-        assert_eq!(
-            Packet::new(
-                PacketId::Disconnect,
-                "/admin".to_owned(),
-                None,
-                None,
-                0,
-                None,
-            ),
-            packet.unwrap()
-        );
+        Ok(())
+    }
 
-        let payload = Bytes::from_static(b"2[\"hello\",1]");
-        let packet = Packet::try_from(&payload);
-        assert!(packet.is_ok());
+    /// Checks that an outgoing event doesn't use one of the names reserved
+    /// by `socket.io`, like `connect` or `disconnect`. Packets other than
+    /// events always pass.
+    pub fn validate_event_name(&self) -> Result<()> {
+        self.validate_event_name_allowing(&[])
+    }
+
+    /// Like [`Packet::validate_event_name`], but lets the reserved names in
+    /// `allowed` pass.
+    pub fn validate_event_name_allowing(&self, allowed: &[&str]) -> Result<()> {
+        let data = self.data.as_deref().unwrap_or_default().trim();
+
+        // binary packets store their data without the surrounding brackets
+        let data = match self.packet_type {
+            PacketId::Event => data.strip_prefix('[').unwrap_or(data),
+            PacketId::BinaryEvent => data,
+            _ => return Ok(()),
+        };
+
+        match (Args { rest: data }).next() {
+            Some(Ok(Value::String(name)))
+                if RESERVED_EVENTS.contains(&name.as_str())
+                    && !allowed.contains(&name.as_str()) =>
+            {
+                Err(Error::ReservedEventName(name))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the namespace of the packet without a query string, e.g.
+    /// `/admin` for `/admin?token=abc`. The raw namespace stays in `nsp`.
+    pub fn namespace(&self) -> &str {
+        self.nsp
+            .split_once('?')
+            .map_or(self.nsp.as_str(), |(path, _)| path)
+    }
+
+    /// Returns the query string some clients append to the namespace,
+    /// without the leading `?`, e.g. `token=abc` for `/admin?token=abc`.
+    pub fn namespace_query(&self) -> Option<&str> {
+        self.nsp.split_once('?').map(|(_, query)| query)
+    }
+
+    /// Returns whether the packet belongs to the default namespace `/`,
+    /// which is left out when the packet is encoded.
+    pub fn is_default_namespace(&self) -> bool {
+        self.nsp == "/"
+    }
+
+    /// Returns whether the packet belongs to the namespace `nsp`. Trailing
+    /// slashes and the query are ignored, so `/admin/?token=abc` matches
+    /// `/admin`.
+    pub fn matches_namespace(&self, nsp: &str) -> bool {
+        self.namespace().trim_end_matches('/') == nsp.trim_end_matches('/')
+    }
+
+    /// Returns an iterator over the `/` delimited segments of the namespace,
+    /// e.g. `tenant` and `admin` for `/tenant/admin`. The default namespace
+    /// has no segments.
+    pub fn namespace_segments(&self) -> impl Iterator<Item = &str> {
+        self.namespace()
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+    }
+
+    /// Lets `f` insert, remove or reorder the arguments of an event or an
+    /// ack and serializes them back into the data. The event name is not
+    /// part of the arguments and always stays in front.
+    pub fn reorder_args<F: FnOnce(&mut Vec<Value>)>(&mut self, f: F) -> Result<()> {
+        let data = self.data.as_deref();
+
+        // binary packets store their data without the surrounding brackets
+        let mut values: Vec<Value> = match self.packet_type {
+            PacketId::Event | PacketId::Ack => serde_json::from_str(data.unwrap_or("[]"))?,
+            PacketId::BinaryEvent | PacketId::BinaryAck => {
+                serde_json::from_str(&format!("[{}]", data.unwrap_or_default()))?
+            }
+            _ => return Err(Error::InvalidPacket()),
+        };
+
+        let event_name = matches!(self.packet_type, PacketId::Event | PacketId::BinaryEvent);
+        let mut args = values.split_off(usize::from(event_name).min(values.len()));
+        f(&mut args);
+        values.append(&mut args);
+
+        self.data = match self.packet_type {
+            PacketId::BinaryEvent | PacketId::BinaryAck => join_binary_args(&values),
+            _ => Some(Value::Array(values).to_string()),
+        };
+
+        Ok(())
+    }
+
+    /// Checks that the namespace starts with a `/` and contains no control
+    /// characters. Any other UTF-8 character is allowed.
+    pub fn validate_utf8_namespace(&self) -> Result<()> {
+        if !self.nsp.starts_with('/') || self.nsp.chars().any(char::is_control) {
+            return Err(Error::InvalidNamespace(self.nsp.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Returns an upper bound for the length of the encoded packet, e.g. to
+    /// reserve the capacity of a buffer up front.
+    pub fn estimated_encoded_len(&self) -> usize {
+        let data_len = self.data.as_ref().map_or(0, String::len);
+        ENCODED_HEADER_LEN
+            + self.nsp.len()
+            + data_len
+            + usize::from(self.attachment_count) * ENCODED_PLACEHOLDER_LEN
+    }
+
+    /// Encodes the packet a single time, so that it can be sent repeatedly
+    /// without encoding it again.
+    pub fn encode_once(self) -> EncodedPacket {
+        EncodedPacket::from(&self)
+    }
+
+    /// Returns the fields in which `self` and `other` differ, which makes
+    /// failing packet comparisons in tests easier to read. The attachments
+    /// themselves are not compared, only their count.
+    pub fn diff(&self, other: &Packet) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+
+        if self.packet_type != other.packet_type {
+            diffs.push(FieldDiff::PacketType(self.packet_type, other.packet_type));
+        }
+        if self.nsp != other.nsp {
+            diffs.push(FieldDiff::Namespace(self.nsp.clone(), other.nsp.clone()));
+        }
+        if self.id != other.id {
+            diffs.push(FieldDiff::Id(self.id, other.id));
+        }
+        if self.data != other.data {
+            diffs.push(FieldDiff::Data(self.data.clone(), other.data.clone()));
+        }
+        if self.attachment_count != other.attachment_count {
+            diffs.push(FieldDiff::AttachmentCount(
+                self.attachment_count,
+                other.attachment_count,
+            ));
+        }
+
+        diffs
+    }
+}
+
+/// A packet that has been encoded once, e.g. to broadcast it to many
+/// sockets. Every call to [`EncodedPacket::bytes`] hands out a reference
+/// counted clone of the same buffer instead of encoding the packet again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodedPacket {
+    bytes: Bytes,
+}
+
+impl EncodedPacket {
+    /// Returns the encoded packet, sharing the buffer with all other clones.
+    pub fn bytes(&self) -> Bytes {
+        self.bytes.clone()
+    }
+}
+
+impl From<&Packet> for EncodedPacket {
+    fn from(packet: &Packet) -> Self {
+        EncodedPacket {
+            bytes: Bytes::from(packet),
+        }
+    }
+}
+
+impl From<EncodedPacket> for Bytes {
+    fn from(packet: EncodedPacket) -> Self {
+        packet.bytes
+    }
+}
+
+/// A field in which two packets differ, as returned by [`Packet::diff`].
+/// Each variant holds the value of the left packet followed by the value of
+/// the right one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDiff {
+    PacketType(PacketId, PacketId),
+    Namespace(String, String),
+    Id(Option<i32>, Option<i32>),
+    Data(Option<String>, Option<String>),
+    AttachmentCount(u8, u8),
+}
+
+/// The event names `socket.io` uses itself, which applications must not emit.
+const RESERVED_EVENTS: [&str; 7] = [
+    "connect",
+    "connect_error",
+    "disconnect",
+    "disconnecting",
+    "error",
+    "newListener",
+    "removeListener",
+];
+
+/// Iterator over the comma separated JSON values of a packet's data. It
+/// ends after the first value that fails to parse.
+struct Args<'a> {
+    rest: &'a str,
+}
+
+impl Iterator for Args<'_> {
+    type Item = Result<Value>;
+
+    fn next(&mut self) -> Option<Result<Value>> {
+        let mut stream = serde_json::Deserializer::from_str(self.rest).into_iter::<Value>();
+        let value = match stream.next()? {
+            Ok(value) => value,
+            Err(err) => {
+                let snippet = json_snippet(self.rest);
+                self.rest = "";
+                return Some(Err(Error::InvalidJsonData(err, snippet)));
+            }
+        };
+
+        let rest = self.rest[stream.byte_offset()..].trim_start();
+        self.rest = rest.strip_prefix(',').unwrap_or(rest);
+
+        Some(Ok(value))
+    }
+}
+
+impl From<Packet> for Bytes {
+    fn from(packet: Packet) -> Self {
+        Bytes::from(&packet)
+    }
+}
+
+impl From<&Packet> for Bytes {
+    /// Method for encoding from a `Packet` to a `u8` byte stream.
+    /// The binary payload of a packet is not put at the end of the
+    /// stream as it gets handled and send by it's own logic via the socket.
+    fn from(packet: &Packet) -> Bytes {
+        let mut numbers = itoa::Buffer::new();
+        let mut buffer = BytesMut::with_capacity(packet.estimated_encoded_len());
+
+        // first the packet type
+        buffer.put_u8(packet.packet_type as u8 + b'0');
+
+        // eventually a number of attachments, followed by '-'
+        if let PacketId::BinaryAck | PacketId::BinaryEvent = packet.packet_type {
+            buffer.put_slice(numbers.format(packet.attachment_count).as_bytes());
+            buffer.put_u8(b'-');
+        }
+
+        // if the namespace is different from the default one append it as well,
+        // followed by ','
+        if !packet.is_default_namespace() {
+            buffer.put_slice(packet.nsp.as_bytes());
+            buffer.put_u8(b',');
+        }
+
+        // if an id is present append it...
+        if let Some(id) = packet.id {
+            buffer.put_slice(numbers.format(id).as_bytes());
+        }
+
+        // binary packets store their data without the brackets and the
+        // placeholders, no matter whether the attachments are present
+        if let PacketId::BinaryAck | PacketId::BinaryEvent = packet.packet_type {
+            buffer.put_u8(b'[');
+
+            // check if an event type is present
+            if let Some(event_type) = packet.data.as_ref() {
+                buffer.put_slice(event_type.as_bytes());
+                if packet.attachment_count > 0 {
+                    buffer.put_u8(b',');
+                }
+            }
+
+            // one placeholder per attachment, in the order they are sent
+            for num in 0..packet.attachment_count {
+                if num > 0 {
+                    buffer.put_u8(b',');
+                }
+                buffer.put_slice(b"{\"_placeholder\":true,\"num\":");
+                buffer.put_slice(numbers.format(num).as_bytes());
+                buffer.put_u8(b'}');
+            }
+
+            buffer.put_u8(b']');
+        } else if let Some(data) = packet.data.as_ref() {
+            buffer.put_slice(data.as_bytes());
+        }
+
+        buffer.freeze()
+    }
+}
+
+/// Upper bound for the length of the type, attachment count, the separators
+/// and the id of an encoded packet.
+const ENCODED_HEADER_LEN: usize = 1 + 3 + 1 + 1 + 11 + 2;
+
+/// Upper bound for the length of a single encoded placeholder.
+const ENCODED_PLACEHOLDER_LEN: usize = "{\"_placeholder\":true,\"num\":255},".len();
+
+impl TryFrom<Bytes> for Packet {
+    type Error = Error;
+    fn try_from(value: Bytes) -> Result<Self> {
+        Packet::try_from(&value)
+    }
+}
+
+impl TryFrom<&Bytes> for Packet {
+    type Error = Error;
+    fn try_from(payload: &Bytes) -> Result<Self> {
+        Packet::try_from(&payload[..])
+    }
+}
+
+impl TryFrom<Vec<u8>> for Packet {
+    type Error = Error;
+    fn try_from(payload: Vec<u8>) -> Result<Self> {
+        Packet::try_from(&payload[..])
+    }
+}
+
+impl TryFrom<&[u8]> for Packet {
+    type Error = Error;
+    /// Decodes a packet given a byte slice.
+    /// The binary payload of a packet is not put at the end of the
+    /// stream as it gets handled and send by it's own logic via the socket.
+    /// Therefore this method does not return the correct value for the
+    /// binary data, instead the socket is responsible for handling
+    /// this member. This is done because the attachment is usually
+    /// send in another packet.
+    fn try_from(payload: &[u8]) -> Result<Packet> {
+        let mut packet = Packet::default();
+        packet.decode_into(payload)?;
+        Ok(packet)
+    }
+}
+
+/// Checks that the placeholders in the data of a binary packet refer to
+/// exactly the attachments `0..attachment_count`, each of them only once.
+/// Only the values of `data` itself are placeholders, an object nested in
+/// an argument is left to the user. In `strict` mode placeholders have to
+/// be written exactly like the encoder does.
+fn validate_placeholders(data: &str, attachment_count: u8, strict: bool) -> Result<()> {
+    let mut referenced = vec![false; usize::from(attachment_count)];
+
+    for (start, end) in top_level_values(data) {
+        let value = &data[start..end];
+        let Some(num) = parse_placeholder(value)? else {
+            continue;
+        };
+        if strict && parse_encoded_placeholder(value).is_none() {
+            return Err(Error::InvalidPacket());
+        }
+        let slot = usize::try_from(num)
+            .ok()
+            .and_then(|num| referenced.get_mut(num))
+            .ok_or(Error::AttachmentIndexOutOfRange(num))?;
+        if *slot {
+            return Err(Error::DuplicateAttachmentIndex(num));
+        }
+        *slot = true;
+    }
+
+    match (0..attachment_count).find(|&num| !referenced[usize::from(num)]) {
+        Some(num) => Err(Error::MissingAttachmentIndex(num)),
+        None => Ok(()),
+    }
+}
+
+/// Removes the placeholders from the comma separated values of a binary
+/// packet's data in a single pass. The data is only copied if a value that
+/// is kept follows a placeholder, otherwise a prefix of it is borrowed.
+fn strip_placeholders(data: &str) -> Cow<'_, str> {
+    // the end of the values in front of the first placeholder
+    let mut prefix_end = 0;
+    let mut seen_placeholder = false;
+    let mut stripped: Option<String> = None;
+
+    for (start, end) in top_level_values(data) {
+        let value = &data[start..end];
+
+        if matches!(parse_placeholder(value), Ok(Some(_))) {
+            seen_placeholder = true;
+        } else if !seen_placeholder {
+            prefix_end = end;
+        } else {
+            let stripped = stripped.get_or_insert_with(|| data[..prefix_end].to_owned());
+            if !stripped.is_empty() {
+                stripped.push(',');
+            }
+            stripped.push_str(value);
+        }
+    }
+
+    match stripped {
+        Some(stripped) => Cow::Owned(stripped),
+        None => Cow::Borrowed(&data[..prefix_end]),
+    }
+}
+
+/// Returns the byte ranges of the comma separated JSON values in `data`.
+/// Iteration stops at the first value that is not valid JSON.
+fn top_level_values(data: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let rest = data.get(offset..).filter(|rest| !rest.is_empty())?;
+        let start = offset + (rest.len() - rest.trim_start().len());
+        let mut values =
+            serde_json::Deserializer::from_str(&data[start..]).into_iter::<IgnoredAny>();
+        let Some(Ok(_)) = values.next() else {
+            offset = data.len();
+            return None;
+        };
+        let end = start + values.byte_offset();
+
+        let rest = data[end..].trim_start();
+        offset = data.len() - rest.strip_prefix(',').unwrap_or(rest).len();
+        Some((start, end))
+    })
+}
+
+/// Returns the attachment index if `value` is a placeholder, an object with
+/// just `"_placeholder": true` and a `num`. Fails with `InvalidPacket` if it
+/// is marked as a placeholder but has no valid `num`.
+fn parse_placeholder(value: &str) -> Result<Option<u64>> {
+    if let Some(num) = parse_encoded_placeholder(value) {
+        return Ok(Some(num));
+    }
+
+    if !value.starts_with('{') {
+        return Ok(None);
+    }
+    let Ok(Value::Object(map)) = serde_json::from_str(value) else {
+        return Ok(None);
+    };
+    if map.get("_placeholder") != Some(&Value::Bool(true)) {
+        return Ok(None);
+    }
+
+    match map.get("num").and_then(Value::as_u64) {
+        Some(num) if map.len() == 2 => Ok(Some(num)),
+        _ => Err(Error::InvalidPacket()),
+    }
+}
+
+/// Returns the attachment index if `value` is a placeholder in exactly the
+/// form the encoder writes.
+fn parse_encoded_placeholder(value: &str) -> Option<u64> {
+    value
+        .strip_prefix("{\"_placeholder\":true,\"num\":")
+        .and_then(|num| num.strip_suffix('}'))
+        .and_then(|num| num.parse().ok())
+}
+
+/// The maximum number of bytes of invalid data that are kept in an error.
+const JSON_SNIPPET_LEN: usize = 128;
+
+/// Serializes `values` as the data of a binary packet, which is stored
+/// without the surrounding brackets. No values result in no data.
+fn join_binary_args(values: &[Value]) -> Option<String> {
+    let data = values
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+    (!data.is_empty()).then_some(data)
+}
+
+/// Serializes the arguments of an event or ack. A value serializing to a
+/// JSON array is spread into multiple arguments and `()` results in none.
+fn serialize_args<T: Serialize>(args: T) -> Result<Vec<Value>> {
+    Ok(match serde_json::to_value(args)? {
+        Value::Array(args) => args,
+        Value::Null => Vec::new(),
+        arg => vec![arg],
+    })
+}
+
+/// Copies the start of `data`, truncated to at most `JSON_SNIPPET_LEN` bytes
+/// on a character boundary.
+fn json_snippet(data: &str) -> String {
+    let mut end = data.len().min(JSON_SNIPPET_LEN);
+    while !data.is_char_boundary(end) {
+        end -= 1;
+    }
+    data[..end].to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    /// This test suite is taken from the explanation section here:
+    /// https://github.com/socketio/socket.io-protocol
+    fn test_decode() {
+        let payload = Bytes::from_static(b"0{\"token\":\"123\"}");
+        let packet = Packet::try_from(&payload);
+        assert!(packet.is_ok());
+
+        assert_eq!(
+            Packet::new(
+                PacketId::Connect,
+                "/".to_owned(),
+                Some(String::from("{\"token\":\"123\"}")),
+                None,
+                0,
+                None,
+            ),
+            packet.unwrap()
+        );
+
+        let utf8_data = "{\"token™\":\"123\"}".to_owned();
+        let utf8_payload = format!("0/admin™,{}", utf8_data);
+        let payload = Bytes::from(utf8_payload);
+        let packet = Packet::try_from(&payload);
+        assert!(packet.is_ok());
+
+        assert_eq!(
+            Packet::new(
+                PacketId::Connect,
+                "/admin™".to_owned(),
+                Some(utf8_data),
+                None,
+                0,
+                None,
+            ),
+            packet.unwrap()
+        );
+
+        let payload = Bytes::from_static(b"1/admin,");
+        let packet = Packet::try_from(&payload);
+        assert!(packet.is_ok());
+
+        assert_eq!(
+            Packet::new(
+                PacketId::Disconnect,
+                "/admin".to_owned(),
+                None,
+                None,
+                0,
+                None,
+            ),
+            packet.unwrap()
+        );
+
+        let payload = Bytes::from_static(b"2[\"hello\",1]");
+        let packet = Packet::try_from(&payload);
+        assert!(packet.is_ok());
 
         assert_eq!(
             Packet::new(
@@ -545,4 +1565,1551 @@ mod test {
         let _sut = PacketId::try_from(42).expect_err("error!");
         assert!(matches!(Error::InvalidPacketId(42 as char), _sut))
     }
+
+    #[test]
+    fn test_packet_id_all() {
+        assert_eq!(PacketId::all().len(), 7);
+
+        for &packet_id in PacketId::all() {
+            assert_eq!(PacketId::try_from(packet_id as u8).ok(), Some(packet_id));
+            assert_eq!(
+                PacketId::try_from(packet_id as u8 + b'0').ok(),
+                Some(packet_id)
+            );
+        }
+    }
+
+    #[test]
+    fn test_packet_id_from_raw_and_ascii() {
+        for packet_id in [2u8, b'2'] {
+            assert_eq!(PacketId::try_from(packet_id).ok(), Some(PacketId::Event));
+        }
+        assert_eq!(PacketId::try_from(6u8).ok(), Some(PacketId::BinaryAck));
+        assert_eq!(PacketId::try_from(b'0').ok(), Some(PacketId::Connect));
+        assert!(PacketId::try_from(7u8).is_err());
+        assert!(PacketId::try_from(b'7').is_err());
+    }
+
+    #[test]
+    fn test_packet_id_ordering() {
+        use std::collections::BTreeMap;
+
+        let ids = [
+            PacketId::BinaryAck,
+            PacketId::BinaryEvent,
+            PacketId::ConnectError,
+            PacketId::Ack,
+            PacketId::Event,
+            PacketId::Disconnect,
+            PacketId::Connect,
+        ];
+        let counters: BTreeMap<PacketId, u64> = ids.iter().map(|&id| (id, 1)).collect();
+
+        assert_eq!(
+            counters.keys().map(|&id| id as u8).collect::<Vec<_>>(),
+            [0, 1, 2, 3, 4, 5, 6]
+        );
+    }
+
+    #[test]
+    fn test_decode_from_engineio_payload() {
+        use rust_engineio::packet::{Payload, ProtocolVersion};
+
+        // an engine.io ping followed by a message carrying a socket.io event
+        let payload = Bytes::from_static(b"2\x1e42/admin,[\"hello\",1]");
+        let packets = Payload::decode(payload, ProtocolVersion::V4).unwrap();
+
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].packet_id, EnginePacketId::Ping);
+        assert!(packets[0].data.is_empty());
+        assert_eq!(packets[1].packet_id, EnginePacketId::Message);
+
+        assert_eq!(
+            Packet::new(
+                PacketId::Event,
+                "/admin".to_owned(),
+                Some(String::from("[\"hello\",1]")),
+                None,
+                0,
+                None,
+            ),
+            Packet::try_from(&packets[1].data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decode_from_vec_and_slice() {
+        let expected = Packet::new(
+            PacketId::Event,
+            "/admin".to_owned(),
+            Some(String::from("[\"hello\"]")),
+            Some(456),
+            0,
+            None,
+        );
+        let payload = b"2/admin,456[\"hello\"]";
+
+        assert_eq!(Packet::try_from(payload.to_vec()).unwrap(), expected);
+        assert_eq!(Packet::try_from(&payload[..]).unwrap(), expected);
+        assert_eq!(
+            Packet::try_from(Bytes::from_static(payload)).unwrap(),
+            expected
+        );
+
+        assert!(matches!(
+            Packet::try_from(&[b'2', 0xff][..]),
+            Err(Error::InvalidUtf8(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_namespace_with_digits() {
+        let payload = Bytes::from_static(b"2/v2,456[\"ev\"]");
+        let packet = Packet::try_from(&payload).unwrap();
+
+        assert_eq!(packet.nsp, "/v2");
+        assert_eq!(packet.id, Some(456));
+        assert_eq!(packet.data.as_deref(), Some("[\"ev\"]"));
+        assert_eq!(Bytes::from(&packet), payload);
+    }
+
+    #[test]
+    fn test_decode_signed_ack_id() {
+        let payload = Bytes::from_static(b"2-5[\"x\"]");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::InvalidAckId(id)) if id == "-5"
+        ));
+
+        let payload = Bytes::from_static(b"2/admin,-5[\"hello\"]");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::InvalidAckId(id)) if id == "-5"
+        ));
+
+        let payload = Bytes::from_static(b"3+456[]");
+        let err = Packet::try_from(&payload).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid ack id: +456");
+    }
+
+    #[test]
+    fn test_event_and_ack_encode_id_alike() {
+        let event = Packet::new(
+            PacketId::Event,
+            "/admin".to_owned(),
+            Some(String::from("[\"hello\"]")),
+            Some(5),
+            0,
+            None,
+        );
+        let ack = Packet {
+            packet_type: PacketId::Ack,
+            ..event.clone()
+        };
+
+        let event = Bytes::from(&event);
+        let ack = Bytes::from(&ack);
+        assert_eq!(event, Bytes::from_static(b"2/admin,5[\"hello\"]"));
+        assert_eq!(ack, Bytes::from_static(b"3/admin,5[\"hello\"]"));
+
+        // only the packet type differs
+        assert_eq!(event[1..], ack[1..]);
+        assert_eq!(Packet::try_from(&event).unwrap().id, Some(5));
+        assert_eq!(Packet::try_from(&ack).unwrap().id, Some(5));
+    }
+
+    #[test]
+    fn test_malformed_input_does_not_panic() {
+        let cases: [(&'static [u8], Option<IncompleteReason>); 8] = [
+            (b"5-[", None),
+            (b"5", Some(IncompleteReason::MissingAttachmentDash)),
+            (b"6", Some(IncompleteReason::MissingAttachmentDash)),
+            (b"", Some(IncompleteReason::EmptyInput)),
+            (b"5-", None),
+            (b"599999-[]", None),
+            (
+                b"2/admin[\"x\"]",
+                Some(IncompleteReason::MissingNamespaceComma),
+            ),
+            (b"299999999999[]", None),
+        ];
+
+        for (payload, reason) in cases {
+            let sut = Packet::try_from(&Bytes::from_static(payload));
+            match reason {
+                Some(reason) => assert!(
+                    matches!(sut, Err(Error::IncompletePacket(r)) if r == reason),
+                    "{payload:?}"
+                ),
+                None => assert!(sut.is_err(), "{payload:?}"),
+            }
+        }
+
+        // attachments without an attachment count used to underflow
+        let packet = Packet::new(
+            PacketId::BinaryEvent,
+            "/".to_owned(),
+            None,
+            None,
+            0,
+            Some(vec![]),
+        );
+        assert_eq!(Bytes::from(&packet), Bytes::from_static(b"50-[]"));
+    }
+
+    #[test]
+    fn test_binary_packet_with_attachments_only() -> Result<()> {
+        for payload in [
+            &b"51-[{\"_placeholder\":true,\"num\":0}]"[..],
+            b"61-/admin,7[{\"_placeholder\":true,\"num\":0}]",
+            b"51-[\"upload\",{\"_placeholder\":true,\"num\":0}]",
+        ] {
+            let packet = Packet::try_from(payload)?;
+            assert_eq!(packet.attachments, None);
+            assert_eq!(Bytes::from(&packet), payload, "{payload:?}");
+
+            // the attachments don't change how the packet is encoded
+            let packet = Packet {
+                attachments: Some(vec![Bytes::new(); usize::from(packet.attachment_count)]),
+                ..packet
+            };
+            assert_eq!(Bytes::from(&packet), payload, "{payload:?}");
+        }
+
+        let packet = Packet::try_from(&b"51-[{\"_placeholder\":true,\"num\":0}]"[..])?;
+        assert_eq!(packet.data, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_binary_packet_requires_brackets() {
+        let payloads: [&'static [u8]; 4] = [b"51-[", b"51-]", b"51-", b"61-/admin,456"];
+        for payload in payloads {
+            let sut = Packet::try_from(&Bytes::from_static(payload));
+            assert!(sut.is_err(), "{payload:?}");
+        }
+
+        let payload = Bytes::from_static(b"51-\"hello\"");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::ExpectedJsonArray(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_placeholders() {
+        let payload = Bytes::from_static(b"51-[\"x\",{\"_placeholder\":true,\"num\":7}]");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::AttachmentIndexOutOfRange(7))
+        ));
+
+        // there is a gap at 1
+        let payload = Bytes::from_static(
+            b"53-[\"x\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":2}]",
+        );
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::MissingAttachmentIndex(1))
+        ));
+
+        let payload = Bytes::from_static(b"51-[\"x\"]");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::MissingAttachmentIndex(0))
+        ));
+
+        let payload = Bytes::from_static(
+            b"51-[\"x\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":0}]",
+        );
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::DuplicateAttachmentIndex(0))
+        ));
+
+        let payload = Bytes::from_static(b"51-[\"x\",{\"_placeholder\":true,\"num\":-1}]");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::InvalidPacket())
+        ));
+
+        // only the top level values are placeholders, nested ones are data
+        let payload = Bytes::from_static(
+            b"62-[{\"a\":{\"_placeholder\":true,\"num\":1}},[{\"_placeholder\":true,\"num\":0}]]",
+        );
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::MissingAttachmentIndex(0))
+        ));
+
+        // the order of the keys doesn't matter
+        let payload = Bytes::from_static(b"51-[\"x\",{\"num\":0,\"_placeholder\":true}]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"x\""));
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"51-[\"x\",{\"_placeholder\":true,\"num\":0}]")
+        );
+    }
+
+    #[test]
+    fn test_nested_placeholder_round_trip() {
+        let payload = Bytes::from_static(
+            b"51-[\"x\",{\"a\":{\"_placeholder\":true,\"num\":0}},{\"_placeholder\":true,\"num\":0}]",
+        );
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(
+            packet.data.as_deref(),
+            Some("\"x\",{\"a\":{\"_placeholder\":true,\"num\":0}}")
+        );
+        assert_eq!(Bytes::from(&packet), payload);
+        assert_eq!(Packet::try_from(&Bytes::from(&packet)).unwrap(), packet);
+    }
+
+    #[test]
+    /// Decoding has to stay linear in the size of the body. A body eight
+    /// times as large takes about eight times as long to decode with a
+    /// linear decoder, and about 64 times as long with a quadratic one. The
+    /// bound in between leaves room for noisy machines, the decode
+    /// benchmarks keep track of the actual timings.
+    fn test_decode_large_body() {
+        use std::hint::black_box;
+        use std::time::{Duration, Instant};
+
+        fn args(count: usize) -> String {
+            (0..count)
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        }
+
+        /// Returns the fastest of a few runs, which is the one least
+        /// affected by other work on the machine.
+        fn decode_time(payload: &Bytes) -> Duration {
+            (0..3)
+                .map(|_| {
+                    let start = Instant::now();
+                    black_box(Packet::try_from(payload).unwrap());
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        }
+
+        let small = args(62_500);
+        let large = args(500_000);
+        assert!(large.len() > 3_000_000);
+
+        let event = |args: &str| Bytes::from(format!("2[\"big\",{args}]"));
+        let binary_event = |args: &str| {
+            Bytes::from(format!(
+                "51-[\"big\",{args},{{\"_placeholder\":true,\"num\":0}}]"
+            ))
+        };
+
+        for encode in [&event as &dyn Fn(&str) -> Bytes, &binary_event] {
+            let ratio = decode_time(&encode(&large)).as_secs_f64()
+                / decode_time(&encode(&small)).as_secs_f64();
+            assert!(ratio < 32.0, "an 8x larger body took {ratio:.1}x as long");
+        }
+
+        let packet = Packet::try_from(&event(&large)).unwrap();
+        assert_eq!(packet.data.map(|data| data.len()), Some(large.len() + 8));
+
+        let packet = Packet::try_from(&binary_event(&large)).unwrap();
+        assert_eq!(packet.data, Some(format!("\"big\",{large}")));
+    }
+
+    #[test]
+    fn test_decode_header_only() {
+        let payload = Bytes::from_static(
+            b"51-/admin,456[\"project:delete\",{\"_placeholder\":true,\"num\":0}]",
+        );
+        assert_eq!(
+            Packet::new(
+                PacketId::BinaryEvent,
+                "/admin".to_owned(),
+                None,
+                Some(456),
+                1,
+                None,
+            ),
+            Packet::decode_header_only(&payload).unwrap()
+        );
+
+        // the body is not validated
+        let payload = Bytes::from_static(b"2/admin,7[not json");
+        let packet = Packet::decode_header_only(&payload).unwrap();
+        assert_eq!(packet.packet_type, PacketId::Event);
+        assert_eq!(packet.nsp, "/admin");
+        assert_eq!(packet.id, Some(7));
+        assert_eq!(packet.data, None);
+        assert!(Packet::try_from(&payload).is_err());
+    }
+
+    #[test]
+    fn test_decode_too_many_attachments() {
+        fn too_many<T>(result: Result<T>, count: u64) -> bool {
+            matches!(result, Err(Error::TooManyAttachments(announced)) if announced == count)
+        }
+
+        for count in [65, 255, 1000] {
+            let payload = Bytes::from(format!("5{count}-[\"upload\"]"));
+
+            assert!(too_many(Packet::try_from(&payload), count));
+            assert!(too_many(Packet::try_from_strict(&payload), count));
+            assert!(too_many(Packet::decode_header_only(&payload), count));
+            assert!(too_many(Packet::default().decode_into(&payload), count));
+        }
+
+        // the limit itself is accepted
+        let placeholders = (0..Packet::DEFAULT_MAX_ATTACHMENTS)
+            .map(|num| format!("{{\"_placeholder\":true,\"num\":{num}}}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        let payload = Bytes::from(format!("564-[\"upload\",{placeholders}]"));
+        assert_eq!(Packet::try_from(&payload).unwrap().attachment_count, 64);
+    }
+
+    #[test]
+    fn test_iter_args() {
+        let payload = Bytes::from_static(b"2[\"hello\",1,{\"a\":[2,3]}, \"x,y\"]");
+        let packet = Packet::try_from(&payload).unwrap();
+
+        assert_eq!(
+            packet
+                .iter_args()
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+            vec![
+                serde_json::json!(1),
+                serde_json::json!({"a": [2, 3]}),
+                serde_json::json!("x,y")
+            ]
+        );
+
+        // acks don't carry an event name
+        let payload = Bytes::from_static(b"3/admin,456[\"hello\",1]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(
+            packet
+                .iter_args()
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+            vec![serde_json::json!("hello"), serde_json::json!(1)]
+        );
+
+        let payload = Bytes::from_static(b"51-[\"hello\",2,{\"_placeholder\":true,\"num\":0}]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(
+            packet
+                .iter_args()
+                .unwrap()
+                .collect::<Result<Vec<_>>>()
+                .unwrap(),
+            vec![serde_json::json!(2)]
+        );
+
+        let payload = Bytes::from_static(b"0{\"token\":\"123\"}");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert!(packet.iter_args().is_err());
+    }
+
+    #[test]
+    fn test_iter_args_stops_early() {
+        // the arguments are only parsed once the iterator reaches them
+        let packet = Packet::new(
+            PacketId::Event,
+            "/".to_owned(),
+            Some(String::from("[\"hello\",1,not json]")),
+            None,
+            0,
+            None,
+        );
+
+        let mut args = packet.iter_args().unwrap();
+        assert_eq!(args.next().unwrap().unwrap(), serde_json::json!(1));
+
+        // an argument that fails to parse is reported instead of ending silently
+        assert!(matches!(
+            args.next(),
+            Some(Err(Error::InvalidJsonData(_, snippet))) if snippet.starts_with("not json")
+        ));
+        assert!(args.next().is_none());
+    }
+
+    #[test]
+    fn test_binary_event_from_iter() {
+        let attachments = (1..=2).map(|i| Bytes::from(vec![i; 3]));
+        let packet = Packet::binary_event_from_iter("hello", "/admin", attachments).unwrap();
+
+        assert_eq!(
+            Packet::new(
+                PacketId::BinaryEvent,
+                "/admin".to_owned(),
+                Some(String::from("\"hello\"")),
+                None,
+                2,
+                Some(vec![
+                    Bytes::from_static(&[1, 1, 1]),
+                    Bytes::from_static(&[2, 2, 2])
+                ]),
+            ),
+            packet
+        );
+    }
+
+    #[test]
+    fn test_namespace() {
+        let namespace = Namespace::new("/admin").unwrap();
+        assert_eq!(&*namespace, "/admin");
+        assert_eq!(namespace.to_string(), "/admin");
+        assert_eq!(&*Namespace::default(), "/");
+
+        let packet = Packet {
+            nsp: namespace.into(),
+            ..Default::default()
+        };
+        assert_eq!(packet.nsp, "/admin");
+
+        assert!(matches!(
+            Namespace::new("/ad,min"),
+            Err(Error::InvalidNamespace(nsp)) if nsp == "/ad,min"
+        ));
+        assert!(Namespace::new("admin").is_err());
+        assert!(Namespace::new("").is_err());
+    }
+
+    #[test]
+    fn test_from_event() {
+        let packet = Packet::from_event("/", "msg", (1, "two")).unwrap();
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"2[\"msg\",1,\"two\"]")
+        );
+
+        let packet = Packet::from_event("/admin", "msg", "hello").unwrap();
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"2/admin,[\"msg\",\"hello\"]")
+        );
+
+        let packet = Packet::from_event("/", "msg", serde_json::json!({ "a": 1 })).unwrap();
+        assert_eq!(packet.data.as_deref(), Some("[\"msg\",{\"a\":1}]"));
+
+        let packet = Packet::from_event("/", "ping", ()).unwrap();
+        assert_eq!(packet.data.as_deref(), Some("[\"ping\"]"));
+    }
+
+    #[test]
+    fn test_ack() {
+        let packet = Packet::ack("/", 456, ("ok", 2)).unwrap();
+        assert_eq!(packet.packet_type, PacketId::Ack);
+        assert_eq!(packet.id, Some(456));
+        assert_eq!(Bytes::from(&packet), Bytes::from_static(b"3456[\"ok\",2]"));
+
+        let packet = Packet::ack("/admin", 1, ()).unwrap();
+        assert_eq!(Bytes::from(&packet), Bytes::from_static(b"3/admin,1[]"));
+    }
+
+    #[test]
+    fn test_ack_id_generator() {
+        let generator = AckIdGenerator::new();
+        assert_eq!(generator.next(), 0);
+        assert_eq!(generator.next(), 1);
+
+        let generator = AckIdGenerator {
+            next: AtomicI32::new(i32::MAX),
+        };
+        assert_eq!(generator.next(), i32::MAX);
+        assert_eq!(generator.next(), 0);
+        assert_eq!(generator.next(), 1);
+
+        let packet = Packet::from_event("/", "msg", ())
+            .unwrap()
+            .with_ack_id(generator.next());
+        assert_eq!(Bytes::from(&packet), Bytes::from_static(b"22[\"msg\"]"));
+    }
+
+    #[test]
+    fn test_with_connect_auth() {
+        let packet = Packet::default()
+            .with_connect_auth(&serde_json::json!({ "token": "123" }))
+            .unwrap();
+
+        assert_eq!(packet.packet_type, PacketId::Connect);
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"0{\"token\":\"123\"}")
+        );
+
+        let packet = Packet {
+            nsp: "/admin".to_owned(),
+            ..Default::default()
+        }
+        .with_connect_auth(&serde_json::json!({ "token": "123" }))
+        .unwrap();
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"0/admin,{\"token\":\"123\"}")
+        );
+    }
+
+    #[test]
+    fn test_from_engine_message() -> Result<()> {
+        let message = EnginePacket::try_from(Bytes::from_static(b"42[\"hi\"]"))?;
+        let packet = Packet::from_engine_message(&message)?;
+        assert_eq!(packet, Packet::from_event("/", "hi", ())?);
+
+        let message = Packet::ack("/admin", 3, "ok")?.to_engineio_message();
+        assert_eq!(
+            Packet::from_engine_message(&message)?,
+            Packet::ack("/admin", 3, "ok")?
+        );
+
+        let ping = EnginePacket::new(EnginePacketId::Ping, Bytes::new());
+        assert!(matches!(
+            Packet::from_engine_message(&ping),
+            Err(Error::IncompleteResponseFromEngineIo(
+                rust_engineio::Error::UnexpectedPacketType(EnginePacketId::Ping)
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_engineio_message() {
+        let packet = Packet::from_event("/admin", "hello", 1).unwrap();
+        let message = packet.to_engineio_message();
+
+        assert_eq!(message.packet_id, EnginePacketId::Message);
+        assert_eq!(message.data, Bytes::from(&packet));
+        assert_eq!(
+            Bytes::from(message),
+            Bytes::from_static(b"42/admin,[\"hello\",1]")
+        );
+
+        // attachments are left out
+        let attachments = [Bytes::from_static(&[1, 2, 3])];
+        let packet = Packet::binary_event_from_iter("hello", "/", attachments).unwrap();
+        let message = packet.to_engineio_message();
+        assert_eq!(
+            message.data,
+            Bytes::from_static(b"51-[\"hello\",{\"_placeholder\":true,\"num\":0}]")
+        );
+    }
+
+    #[test]
+    fn test_clone_shallow() {
+        let attachments = [Bytes::from(vec![1; 1024]), Bytes::from(vec![2; 1024])];
+        let packet = Packet::binary_event_from_iter("hello", "/", attachments).unwrap();
+
+        let clone = packet.clone_shallow();
+        assert_eq!(clone, packet);
+
+        for (original, cloned) in packet
+            .attachments
+            .iter()
+            .flatten()
+            .zip(clone.attachments.iter().flatten())
+        {
+            assert_eq!(original.as_ptr(), cloned.as_ptr());
+        }
+    }
+
+    #[test]
+    fn test_attachment() {
+        let attachments = (1..=2).map(|i| Bytes::from(vec![i; 3]));
+        let packet = Packet::binary_event_from_iter("hello", "/", attachments).unwrap();
+
+        assert_eq!(packet.attachment(0), Some(&Bytes::from_static(&[1, 1, 1])));
+        assert_eq!(packet.attachment(1), Some(&Bytes::from_static(&[2, 2, 2])));
+        assert_eq!(packet.attachment(2), None);
+
+        assert_eq!(Packet::default().attachment(0), None);
+    }
+
+    #[test]
+    fn test_coalesce_attachments() {
+        let attachments = [Bytes::from_static(&[1, 2]), Bytes::from_static(&[3])];
+        let mut packet = Packet::binary_event_from_iter("hello", "/", attachments).unwrap();
+        // a placeholder for the second attachment that is left in the data
+        packet.data = Some(String::from("\"hello\",{\"_placeholder\":true,\"num\":1}"));
+
+        packet.coalesce_attachments();
+        assert_eq!(packet.attachment_count, 1);
+        assert_eq!(
+            packet.attachments,
+            Some(vec![Bytes::from_static(&[1, 2, 3])])
+        );
+        assert_eq!(packet.data.as_deref(), Some("\"hello\""));
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"51-[\"hello\",{\"_placeholder\":true,\"num\":0}]")
+        );
+
+        // a single attachment is left untouched
+        let before = packet.clone();
+        packet.coalesce_attachments();
+        assert_eq!(packet, before);
+    }
+
+    #[test]
+    fn test_coalesce_attachments_keeps_user_data() {
+        let payload = Bytes::from_static(
+            b"52-[\"x\",{\"z\":1,\"a\":{\"_placeholder\":true}},{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]",
+        );
+        let mut packet = Packet::try_from(&payload).unwrap();
+        packet.attachments = Some(vec![Bytes::from_static(&[1]), Bytes::from_static(&[2])]);
+        let data = packet.data.clone();
+
+        packet.coalesce_attachments();
+        assert_eq!(packet.attachment_count, 1);
+        assert_eq!(packet.data, data);
+        assert_eq!(
+            packet.data.as_deref(),
+            Some("\"x\",{\"z\":1,\"a\":{\"_placeholder\":true}}")
+        );
+    }
+
+    #[test]
+    fn test_reorder_args() {
+        let payload = Bytes::from_static(b"2[\"hello\",1,2]");
+        let mut packet = Packet::try_from(&payload).unwrap();
+
+        packet
+            .reorder_args(|args| args.insert(0, serde_json::json!({ "user": "alice" })))
+            .unwrap();
+        assert_eq!(
+            packet.data.as_deref(),
+            Some("[\"hello\",{\"user\":\"alice\"},1,2]")
+        );
+
+        packet.reorder_args(|args| args.reverse()).unwrap();
+        assert_eq!(
+            packet.data.as_deref(),
+            Some("[\"hello\",2,1,{\"user\":\"alice\"}]")
+        );
+
+        // acks have no event name
+        let payload = Bytes::from_static(b"3456[1,2]");
+        let mut packet = Packet::try_from(&payload).unwrap();
+        packet
+            .reorder_args(|args| {
+                args.remove(0);
+            })
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("[2]"));
+
+        // binary packets keep their data without brackets
+        let payload = Bytes::from_static(b"51-[\"hello\",{\"_placeholder\":true,\"num\":0}]");
+        let mut packet = Packet::try_from(&payload).unwrap();
+        packet
+            .reorder_args(|args| args.push(Value::from(1)))
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"hello\",1"));
+
+        let mut packet = Packet::new(PacketId::Connect, "/".to_owned(), None, None, 0, None);
+        assert!(packet.reorder_args(|_| ()).is_err());
+    }
+
+    #[test]
+    fn test_map_data() {
+        let payload = Bytes::from_static(b"2[\"inc\",1]");
+        let mut packet = Packet::try_from(&payload).unwrap();
+
+        packet
+            .map_data(|mut value| {
+                value[1] = Value::from(value[1].as_i64().unwrap() + 1);
+                value
+            })
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("[\"inc\",2]"));
+        assert_eq!(Bytes::from(&packet), Bytes::from_static(b"2[\"inc\",2]"));
+
+        let mut packet = Packet::default();
+        packet
+            .map_data(|value| {
+                assert_eq!(value, Value::Null);
+                serde_json::json!(["hello"])
+            })
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("[\"hello\"]"));
+
+        packet.data = Some("not json".to_owned());
+        assert!(packet.map_data(|value| value).is_err());
+
+        // binary packets with several arguments store them without brackets
+        let payload = Bytes::from_static(b"51-[\"inc\",1,2,{\"_placeholder\":true,\"num\":0}]");
+        let mut packet = Packet::try_from(&payload).unwrap();
+        packet
+            .map_data(|mut value| {
+                assert_eq!(value, serde_json::json!(["inc", 1, 2]));
+                value[2] = Value::from(3);
+                value
+            })
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"inc\",1,3"));
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"51-[\"inc\",1,3,{\"_placeholder\":true,\"num\":0}]")
+        );
+    }
+
+    #[test]
+    fn test_invalid_json_snippet() {
+        let payload = Bytes::from_static(b"2[\"unclosed");
+        let err = Packet::try_from(&payload).unwrap_err();
+
+        assert!(matches!(err, Error::InvalidJsonData(_, ref data) if data == "[\"unclosed"));
+        assert!(err.to_string().contains("`[\"unclosed`"));
+
+        // long data is truncated on a character boundary
+        let payload = Bytes::from(format!("2[\"{}", "™".repeat(100)));
+        let err = Packet::try_from(&payload).unwrap_err();
+
+        let expected = format!("[\"{}", "™".repeat(42));
+        assert!(matches!(err, Error::InvalidJsonData(_, ref data) if *data == expected));
+    }
+
+    #[test]
+    fn test_validate_event_name() {
+        let packet = Packet::from_event("/", "disconnect", ()).unwrap();
+        assert!(matches!(
+            packet.validate_event_name(),
+            Err(Error::ReservedEventName(name)) if name == "disconnect"
+        ));
+        assert!(packet.validate_event_name_allowing(&["disconnect"]).is_ok());
+
+        let packet = Packet::binary_event_from_iter("error", "/", []).unwrap();
+        assert!(packet.validate_event_name().is_err());
+
+        let packet = Packet::from_event("/", "chat message", "hi").unwrap();
+        assert!(packet.validate_event_name().is_ok());
+
+        // only events carry an event name
+        let packet = Packet::new(
+            PacketId::Ack,
+            "/".to_owned(),
+            Some(String::from("[\"connect\"]")),
+            Some(1),
+            0,
+            None,
+        );
+        assert!(packet.validate_event_name().is_ok());
+    }
+
+    #[test]
+    fn test_default_namespace() {
+        for (nsp, is_default) in [
+            ("/", true),
+            ("/admin", false),
+            ("/admin/", false),
+            ("", false),
+        ] {
+            let packet = Packet {
+                nsp: nsp.to_owned(),
+                ..Default::default()
+            };
+            assert_eq!(packet.namespace(), nsp);
+            assert_eq!(packet.is_default_namespace(), is_default, "{nsp:?}");
+        }
+
+        assert!(Packet::default().is_default_namespace());
+    }
+
+    #[test]
+    fn test_multibyte_packet_id() {
+        assert!(matches!(
+            Packet::try_from(Bytes::from("🙂[\"hello\"]")),
+            Err(Error::InvalidPacketId('🙂'))
+        ));
+        assert!(matches!(
+            PacketId::from_digit('7'),
+            Err(Error::InvalidPacketId('7'))
+        ));
+        assert_eq!(PacketId::from_digit('5').ok(), Some(PacketId::BinaryEvent));
+    }
+
+    #[test]
+    fn test_into_and_from_parts() -> Result<()> {
+        let packet =
+            Packet::binary_event_from_iter("upload", "/admin", vec![Bytes::from_static(&[1])])?
+                .with_ack_id(3);
+
+        let (packet_type, nsp, data, id, attachments) = packet.clone().into_parts();
+        assert_eq!(packet_type, PacketId::BinaryEvent);
+        assert_eq!(nsp, "/admin");
+        assert_eq!(data.as_deref(), Some("\"upload\""));
+        assert_eq!(id, Some(3));
+        assert_eq!(attachments, Some(vec![Bytes::from_static(&[1])]));
+
+        let rebuilt = Packet::from_parts(packet_type, nsp, data, id, attachments)?;
+        assert_eq!(rebuilt, packet);
+
+        let sut = Packet::from_parts(
+            PacketId::BinaryEvent,
+            "/".to_owned(),
+            None,
+            None,
+            Some(vec![Bytes::new(); 256]),
+        );
+        assert!(matches!(sut, Err(Error::InvalidPacket())));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_connect_and_disconnect() {
+        let connect = Packet::connect("/admin", Some(serde_json::json!({ "token": "123" })));
+        assert_eq!(connect.packet_type, PacketId::Connect);
+        assert_eq!(
+            Bytes::from(&connect),
+            Bytes::from_static(b"0/admin,{\"token\":\"123\"}")
+        );
+        assert_eq!(
+            Bytes::from(&Packet::connect("/", None)),
+            Bytes::from_static(b"0")
+        );
+
+        let disconnect = Packet::disconnect("/admin");
+        assert_eq!(disconnect.packet_type, PacketId::Disconnect);
+        assert_eq!(Bytes::from(&disconnect), Bytes::from_static(b"1/admin,"));
+    }
+
+    #[test]
+    fn test_encode_once() {
+        let packet = Packet::new(
+            PacketId::Event,
+            "/admin".to_owned(),
+            Some(String::from("[\"hello\"]")),
+            None,
+            0,
+            None,
+        );
+        let expected = Bytes::from(&packet);
+        let encoded = packet.encode_once();
+
+        let first = encoded.bytes();
+        let second = encoded.clone().bytes();
+        assert_eq!(first, expected);
+        assert_eq!(first.as_ptr(), second.as_ptr());
+        assert_eq!(Bytes::from(encoded).as_ptr(), first.as_ptr());
+    }
+
+    #[test]
+    fn test_try_from_strict() {
+        // acks without an id are only accepted by the lenient decoder
+        for payload in [
+            &b"3[\"ok\"]"[..],
+            b"3/admin,[]",
+            b"61-[{\"_placeholder\":true,\"num\":0}]",
+        ] {
+            let payload = Bytes::from_static(payload);
+            assert!(Packet::try_from(&payload).is_ok(), "{payload:?}");
+            assert!(
+                matches!(
+                    Packet::try_from_strict(&payload),
+                    Err(Error::MissingAckId())
+                ),
+                "{payload:?}"
+            );
+        }
+
+        for payload in [
+            &b"0/admin,{\"token\":\"123\"}"[..],
+            b"2[\"msg\",1]",
+            b"3456[\"ok\"]",
+            b"62-/admin,7[{\"_placeholder\":true,\"num\":1},{\"_placeholder\":true,\"num\":0}]",
+        ] {
+            let payload = Bytes::from_static(payload);
+            assert_eq!(
+                Packet::try_from_strict(&payload).unwrap(),
+                Packet::try_from(&payload).unwrap()
+            );
+        }
+
+        // the lenient forms are only accepted by the lenient decoder
+        let payload = Bytes::from_static(b"1/admin");
+        assert!(Packet::try_from(&payload).is_ok());
+        assert!(matches!(
+            Packet::try_from_strict(&payload),
+            Err(Error::IncompletePacket(
+                IncompleteReason::MissingNamespaceComma
+            ))
+        ));
+        for payload in [
+            &b"51-[\"upload\",{\"num\":0,\"_placeholder\":true}]"[..],
+            b"51-[\"upload\",{ \"_placeholder\": true, \"num\": 0 }]",
+        ] {
+            let payload = Bytes::from_static(payload);
+            assert!(Packet::try_from(&payload).is_ok(), "{payload:?}");
+            assert!(
+                matches!(
+                    Packet::try_from_strict(&payload),
+                    Err(Error::InvalidPacket())
+                ),
+                "{payload:?}"
+            );
+        }
+
+        // both decoders share the parsing
+        let payload = Bytes::from_static(b"2{\"x\":1}");
+        assert!(matches!(
+            Packet::try_from(&payload),
+            Err(Error::ExpectedJsonArray(_))
+        ));
+        assert!(matches!(
+            Packet::try_from_strict(&payload),
+            Err(Error::ExpectedJsonArray(_))
+        ));
+    }
+
+    #[test]
+    fn test_try_from_reader() -> Result<()> {
+        let packet = Packet::from_event("/admin", "msg", (1, "two"))?;
+        let mut reader = std::io::Cursor::new(Bytes::from(&packet));
+        assert_eq!(Packet::try_from_reader(&mut reader)?, packet);
+
+        let mut reader = std::io::Cursor::new(b"2[\"x\"");
+        assert!(matches!(
+            Packet::try_from_reader(&mut reader),
+            Err(Error::InvalidJsonData(..))
+        ));
+
+        struct FailingReader;
+
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("oh no"))
+            }
+        }
+
+        assert!(matches!(
+            Packet::try_from_reader(&mut FailingReader),
+            Err(Error::IncompleteIo(_))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_into() -> Result<()> {
+        let mut packet = Packet::try_from(&b"3/admin,7[\"first\"]"[..])?;
+        let nsp = packet.nsp.as_ptr();
+        let data = packet.data.as_ref().map(|data| data.as_ptr());
+
+        packet.decode_into(b"2/admin,[\"again\"]")?;
+        assert_eq!(packet, Packet::try_from(&b"2/admin,[\"again\"]"[..])?);
+        assert_eq!(packet.id, None);
+        assert_eq!(packet.nsp.as_ptr(), nsp);
+        assert_eq!(packet.data.as_ref().map(|data| data.as_ptr()), data);
+
+        packet.decode_into(b"51-[\"x\",{\"_placeholder\":true,\"num\":0}]")?;
+        assert_eq!(packet.packet_type, PacketId::BinaryEvent);
+        assert_eq!(packet.nsp, "/");
+        assert_eq!(packet.data.as_deref(), Some("\"x\""));
+        assert_eq!(packet.attachment_count, 1);
+
+        packet.reset();
+        assert_eq!(packet, Packet::default());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_with_namespace() -> Result<()> {
+        let packet =
+            Packet::binary_event_from_iter("upload", "/", vec![Bytes::from_static(&[1, 2, 3])])?
+                .with_ack_id(7);
+
+        let rerouted = packet.clone_with_namespace("/admin");
+        assert_eq!(
+            packet.diff(&rerouted),
+            [FieldDiff::Namespace("/".to_owned(), "/admin".to_owned())]
+        );
+        assert_eq!(rerouted.attachments, packet.attachments);
+        assert_eq!(
+            Bytes::from(&packet),
+            Bytes::from_static(b"51-7[\"upload\",{\"_placeholder\":true,\"num\":0}]")
+        );
+        assert_eq!(
+            Bytes::from(&rerouted),
+            Bytes::from_static(b"51-/admin,7[\"upload\",{\"_placeholder\":true,\"num\":0}]")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_placeholders() {
+        let cases = [
+            ("", ""),
+            ("\"x\",1", "\"x\",1"),
+            ("\"x\",{\"_placeholder\":true,\"num\":0}", "\"x\""),
+            ("{\"_placeholder\":true,\"num\":0}", ""),
+            (
+                "\"x\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}",
+                "\"x\"",
+            ),
+            (
+                "\"x\",{\"_placeholder\":true,\"num\":0},\"y\",{\"a\":\"{\\\"_placeholder\\\"\"}",
+                "\"x\",\"y\",{\"a\":\"{\\\"_placeholder\\\"\"}",
+            ),
+        ];
+
+        for (data, expected) in cases {
+            assert_eq!(strip_placeholders(data), expected, "{data:?}");
+        }
+
+        // nothing is copied if the placeholders come last
+        assert!(matches!(
+            strip_placeholders("\"x\",{\"_placeholder\":true,\"num\":0}"),
+            Cow::Borrowed(_)
+        ));
+
+        // all placeholders are stripped, so that multiple attachments survive
+        // a round trip
+        let payload = Bytes::from_static(
+            b"52-[\"x\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]",
+        );
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"x\""));
+        assert_eq!(Bytes::from(&packet), payload);
+    }
+
+    #[test]
+    fn test_decode_binary_unbalanced() {
+        for payload in [
+            &b"51-[\"a\","[..],
+            b"51-[\"a\",{\"_placeholder\":true,\"num\":0}",
+            b"51-[{\"_placeholder\":true,\"num\":0},[\"a\"]",
+        ] {
+            assert!(
+                matches!(Packet::try_from(payload), Err(Error::InvalidJsonData(..))),
+                "{payload:?}"
+            );
+        }
+
+        // the values around a placeholder are stitched back together
+        let payload = Bytes::from_static(b"51-[\"a\",{\"_placeholder\":true,\"num\":0},[1]]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"a\",[1]"));
+    }
+
+    #[test]
+    fn test_estimated_encoded_len() {
+        let packets = [
+            Packet::default(),
+            Packet::connect("/admin", Some(serde_json::json!({ "token": "123" }))),
+            Packet::disconnect("/admin"),
+            Packet::new(
+                PacketId::Ack,
+                "/admin".to_owned(),
+                Some(String::from("[\"ok\"]")),
+                Some(i32::MAX),
+                0,
+                None,
+            ),
+            Packet::new(
+                PacketId::BinaryAck,
+                "/admin".to_owned(),
+                None,
+                Some(i32::MAX),
+                255,
+                None,
+            ),
+            Packet::binary_event_from_iter("upload", "/", vec![Bytes::new(); 10]).unwrap(),
+        ];
+
+        for packet in packets {
+            let encoded = Bytes::from(&packet);
+            assert!(
+                packet.estimated_encoded_len() >= encoded.len(),
+                "{packet:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let left = Packet::new(
+            PacketId::Event,
+            "/".to_owned(),
+            Some(String::from("[\"hello\"]")),
+            None,
+            0,
+            None,
+        );
+        let right = Packet {
+            nsp: "/admin".to_owned(),
+            id: Some(1),
+            ..left.clone()
+        };
+
+        assert!(left.diff(&left).is_empty());
+        assert_eq!(
+            left.diff(&right),
+            [
+                FieldDiff::Namespace("/".to_owned(), "/admin".to_owned()),
+                FieldDiff::Id(None, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_namespace_query() -> Result<()> {
+        let payload = Bytes::from_static(b"0/admin?sid=1,{\"x\":1}");
+        let packet = Packet::try_from(&payload)?;
+
+        assert_eq!(packet.nsp, "/admin?sid=1");
+        assert_eq!(packet.namespace(), "/admin");
+        assert_eq!(packet.namespace_query(), Some("sid=1"));
+        assert_eq!(packet.data.as_deref(), Some("{\"x\":1}"));
+        assert_eq!(Bytes::from(&packet), payload);
+
+        let packet = Packet {
+            nsp: "/admin".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(packet.namespace(), "/admin");
+        assert_eq!(packet.namespace_query(), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_matches_namespace() {
+        let packet = Packet::new(PacketId::Event, "/admin".to_owned(), None, None, 0, None);
+        assert!(packet.matches_namespace("/admin"));
+        assert!(packet.matches_namespace("/admin/"));
+        assert!(!packet.matches_namespace("/admins"));
+        assert!(!packet.matches_namespace("/"));
+
+        let packet = Packet::new(
+            PacketId::Event,
+            "/admin/?token=abc".to_owned(),
+            None,
+            None,
+            0,
+            None,
+        );
+        assert!(packet.matches_namespace("/admin"));
+        assert!(!packet.matches_namespace("/admins"));
+        assert_eq!(packet.nsp, "/admin/?token=abc");
+
+        assert!(Packet::default().matches_namespace("/"));
+        assert!(!Packet::default().matches_namespace("/admin"));
+    }
+
+    #[test]
+    fn test_namespace_segments() {
+        let mut packet = Packet {
+            nsp: "/tenant/admin".to_owned(),
+            ..Default::default()
+        };
+        assert_eq!(
+            packet.namespace_segments().collect::<Vec<_>>(),
+            ["tenant", "admin"]
+        );
+
+        packet.nsp = "/".to_owned();
+        assert_eq!(packet.namespace_segments().next(), None);
+    }
+
+    #[test]
+    fn test_reject_trailing_data() {
+        let payload = Bytes::from_static(b"2[\"a\"]x");
+        let err = Packet::try_from(&payload).unwrap_err();
+        assert!(matches!(err, Error::InvalidJsonData(_, ref data) if data == "[\"a\"]x"));
+
+        let payload = Bytes::from_static(b"2[\"a\"][\"b\"]");
+        assert!(Packet::try_from(&payload).is_err());
+
+        // trailing whitespace is no data
+        let payload = Bytes::from_static(b"2[\"a\"] \n");
+        assert!(Packet::try_from(&payload).is_ok());
+    }
+
+    #[test]
+    fn test_connect_error_message() {
+        let payload = Bytes::from_static(b"4/admin,\"nope\"");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.connect_error_message().as_deref(), Some("nope"));
+
+        let payload = Bytes::from_static(b"4/admin,{\"message\":\"Not authorized\"}");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(
+            packet.connect_error_message().as_deref(),
+            Some("Not authorized")
+        );
+
+        let payload = Bytes::from_static(b"4/admin,{\"data\":1}");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.connect_error_message(), None);
+
+        let payload = Bytes::from_static(b"2[\"nope\"]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.connect_error_message(), None);
+    }
+
+    #[test]
+    fn test_decode_trailing_id() -> Result<()> {
+        let packet = Packet::try_from(&b"3456"[..])?;
+        assert_eq!(packet.packet_type, PacketId::Ack);
+        assert_eq!(packet.id, Some(456));
+        assert_eq!(packet.data, None);
+
+        let packet = Packet::try_from(&b"2/admin,7"[..])?;
+        assert_eq!(packet.nsp, "/admin");
+        assert_eq!(packet.id, Some(7));
+        assert_eq!(packet.data, None);
+        assert_eq!(Bytes::from(&packet), Bytes::from_static(b"2/admin,7"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ack_args() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct AckData {
+            ok: bool,
+        }
+
+        let payload = Bytes::from_static(b"3/admin,456[{\"ok\":true}]");
+        let packet = Packet::try_from(&payload).unwrap();
+
+        assert_eq!(packet.ack_id(), Some(456));
+        let (data,): (AckData,) = packet.ack_args().unwrap();
+        assert_eq!(data, AckData { ok: true });
+
+        let payload = Bytes::from_static(b"61-456[1,{\"_placeholder\":true,\"num\":0}]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.ack_id(), Some(456));
+        assert_eq!(packet.ack_args::<Vec<i32>>().unwrap(), [1]);
+
+        // an event expecting an ack is no ack itself
+        let payload = Bytes::from_static(b"2456[\"hello\"]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert_eq!(packet.ack_id(), None);
+        assert!(matches!(
+            packet.ack_args::<Vec<Value>>(),
+            Err(Error::InvalidPacket())
+        ));
+    }
+
+    #[test]
+    fn test_validate_ack_data() {
+        let packet = Packet::new(
+            PacketId::Ack,
+            "/".to_owned(),
+            Some(String::from("{\"not\":\"array\"}")),
+            Some(456),
+            0,
+            None,
+        );
+        assert!(matches!(
+            packet.validate(),
+            Err(Error::ExpectedJsonArray(data)) if data == "{\"not\":\"array\"}"
+        ));
+
+        let packet = Packet::new(PacketId::Ack, "/".to_owned(), None, Some(456), 0, None);
+        assert!(matches!(
+            packet.validate(),
+            Err(Error::ExpectedJsonArray(_))
+        ));
+
+        let payload = Bytes::from_static(b"3456[\"ok\"]");
+        assert!(Packet::try_from(&payload).unwrap().validate().is_ok());
+
+        // binary acks always carry an array, their brackets are checked while decoding
+        let payload = Bytes::from_static(b"61-456[{\"_placeholder\":true,\"num\":0}]");
+        assert!(Packet::try_from(&payload).unwrap().validate().is_ok());
+    }
+
+    #[test]
+    fn test_control_and_data_packets() {
+        let cases = [
+            (PacketId::Connect, true),
+            (PacketId::Disconnect, true),
+            (PacketId::Event, false),
+            (PacketId::Ack, false),
+            (PacketId::ConnectError, true),
+            (PacketId::BinaryEvent, false),
+            (PacketId::BinaryAck, false),
+        ];
+        assert_eq!(cases.len(), PacketId::all().len());
+
+        for (packet_type, is_control) in cases {
+            assert_eq!(packet_type.is_control(), is_control, "{packet_type:?}");
+            assert_eq!(packet_type.is_data(), !is_control, "{packet_type:?}");
+        }
+    }
+
+    #[test]
+    fn test_validate_ack_id() {
+        for packet_type in PacketId::all() {
+            assert_eq!(
+                packet_type.requires_ack_id(),
+                matches!(packet_type, PacketId::Ack | PacketId::BinaryAck)
+            );
+        }
+
+        let packet = Packet::new(
+            PacketId::Ack,
+            "/".to_owned(),
+            Some("[]".to_owned()),
+            None,
+            0,
+            None,
+        );
+        assert!(matches!(packet.validate(), Err(Error::MissingAckId())));
+        assert!(packet.with_ack_id(456).validate().is_ok());
+
+        let payload = Bytes::from_static(b"61-[{\"_placeholder\":true,\"num\":0}]");
+        let packet = Packet::try_from(&payload).unwrap();
+        assert!(matches!(packet.validate(), Err(Error::MissingAckId())));
+
+        // events may or may not ask for an ack
+        let packet = Packet::from_event("/", "msg", ()).unwrap();
+        assert!(packet.validate().is_ok());
+    }
+
+    #[test]
+    fn test_decode_requires_array() {
+        for payload in [
+            &b"2{\"x\":1}"[..],
+            b"3456{\"not\":\"array\"}",
+            b"2/admin,\"x\"",
+            b"51-{\"_placeholder\":true,\"num\":0}",
+        ] {
+            assert!(
+                matches!(Packet::try_from(payload), Err(Error::ExpectedJsonArray(_))),
+                "{payload:?}"
+            );
+        }
+
+        assert!(Packet::try_from(&b"2[\"x\"]"[..]).is_ok());
+        assert!(Packet::try_from(&b"2 [\"x\"]"[..]).is_ok());
+
+        // connect packets carry an object
+        assert!(Packet::try_from(&b"0{\"token\":\"123\"}"[..]).is_ok());
+        assert!(Packet::try_from(&b"4{\"message\":\"Not authorized\"}"[..]).is_ok());
+    }
+
+    #[test]
+    fn test_validate_utf8_namespace() {
+        let mut packet = Packet {
+            nsp: "/admin™".to_owned(),
+            ..Default::default()
+        };
+        assert!(packet.validate_utf8_namespace().is_ok());
+
+        packet.nsp = "/ad\u{7}min".to_owned();
+        assert!(matches!(
+            packet.validate_utf8_namespace(),
+            Err(Error::InvalidNamespace(nsp)) if nsp == "/ad\u{7}min"
+        ));
+
+        packet.nsp = "admin".to_owned();
+        assert!(packet.validate_utf8_namespace().is_err());
+    }
+
+    #[test]
+    /// The ',' after a namespace may be left out if no body follows, this
+    /// holds for the default namespace as well.
+    fn test_decode_root_namespace_without_comma() {
+        for payload in [&b"4/"[..], b"4/,"] {
+            let packet = Packet::try_from(&Bytes::from_static(payload)).unwrap();
+            assert_eq!(packet.packet_type, PacketId::ConnectError);
+            assert_eq!(packet.nsp, "/");
+            assert_eq!(packet.data, None);
+        }
+    }
+
+    #[test]
+    fn test_decode_namespace_without_comma() {
+        let packet = Packet::try_from(&Bytes::from_static(b"0/admin")).unwrap();
+        assert_eq!(packet.packet_type, PacketId::Connect);
+        assert_eq!(packet.nsp, "/admin");
+        assert_eq!(packet.data, None);
+        assert_eq!(packet.id, None);
+
+        // data can't follow the namespace without a ','
+        let sut = Packet::try_from(&Bytes::from_static(b"0/admin{\"token\":1}"));
+        assert!(matches!(
+            sut,
+            Err(Error::IncompletePacket(
+                IncompleteReason::MissingNamespaceComma
+            ))
+        ));
+    }
+
+    mod proptests {
+        use super::*;
+        use proptest::prelude::*;
+        use serde_json::json;
+
+        fn json_arg() -> impl Strategy<Value = Value> {
+            prop_oneof![
+                Just(Value::Null),
+                any::<bool>().prop_map(Value::from),
+                any::<i32>().prop_map(Value::from),
+                "[a-z ,]{0,8}".prop_map(Value::from),
+            ]
+        }
+
+        /// Generates valid packets of the types that carry no attachments.
+        impl Arbitrary for Packet {
+            type Parameters = ();
+            type Strategy = BoxedStrategy<Packet>;
+
+            fn arbitrary_with(_: ()) -> Self::Strategy {
+                let packet_type = prop_oneof![
+                    Just(PacketId::Connect),
+                    Just(PacketId::Disconnect),
+                    Just(PacketId::Event),
+                    Just(PacketId::Ack),
+                    Just(PacketId::ConnectError),
+                ];
+                let nsp = prop_oneof![Just(String::from("/")), "/[a-z0-9_/]{1,12}"];
+
+                (
+                    packet_type,
+                    nsp,
+                    "[a-z_]{1,12}",
+                    prop::collection::vec(json_arg(), 0..4),
+                    prop::option::of(0..i32::MAX),
+                )
+                    .prop_map(|(packet_type, nsp, name, mut args, id)| {
+                        let (data, id) = match packet_type {
+                            PacketId::Connect => (Some(json!({ "token": name }).to_string()), None),
+                            PacketId::Disconnect => (None, None),
+                            PacketId::Event => {
+                                args.insert(0, Value::from(name));
+                                (Some(Value::Array(args).to_string()), id)
+                            }
+                            PacketId::Ack => {
+                                (Some(Value::Array(args).to_string()), Some(id.unwrap_or(0)))
+                            }
+                            _ => (Some(json!({ "message": name }).to_string()), None),
+                        };
+
+                        Packet::new(packet_type, nsp, data, id, 0, None)
+                    })
+                    .boxed()
+            }
+        }
+
+        proptest! {
+            #[test]
+            fn test_roundtrip(packet in any::<Packet>()) {
+                prop_assert_eq!(Packet::try_from(Bytes::from(&packet))?, packet);
+            }
+        }
+    }
 }
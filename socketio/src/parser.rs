@@ -0,0 +1,781 @@
+use crate::error::{Error, IncompleteReason, Result};
+use crate::packet::Packet;
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::iter::FusedIterator;
+
+/// Converts `socket.io` packets from and to the frames that are exchanged
+/// via the underlying `engine.io` connection. Servers can be configured to
+/// use a different parser than the default one, in which case the client
+/// needs to use the matching implementation.
+pub trait Parser {
+    /// Encodes a packet into the frames that have to be sent in order.
+    fn encode(&self, packet: &Packet) -> Vec<Bytes>;
+
+    /// Decodes a packet from all of its frames.
+    fn decode(&self, frames: &[Bytes]) -> Result<Packet>;
+
+    /// Returns how many frames the packet that starts with `frame` consists
+    /// of, including `frame` itself. Defaults to a single frame per packet.
+    fn frame_count(&self, _frame: &Bytes) -> Result<usize> {
+        Ok(1)
+    }
+
+    /// Returns whether the frame at `index` of an encoded packet is sent as
+    /// binary. Defaults to a text frame followed by binary attachments.
+    fn is_binary_frame(&self, index: usize) -> bool {
+        index > 0
+    }
+}
+
+/// The default `socket.io` parser. Packets are encoded as text, followed by
+/// one binary frame per attachment.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultParser;
+
+impl Parser for DefaultParser {
+    fn encode(&self, packet: &Packet) -> Vec<Bytes> {
+        Encoder::encode(packet)
+    }
+
+    fn decode(&self, frames: &[Bytes]) -> Result<Packet> {
+        let (header, attachments) = frames
+            .split_first()
+            .ok_or(Error::IncompletePacket(IncompleteReason::EmptyInput))?;
+        let mut packet = Packet::try_from(header)?;
+
+        if attachments.len() != usize::from(packet.attachment_count) {
+            return Err(Error::InvalidPacket());
+        }
+
+        if !attachments.is_empty() {
+            packet.attachments = Some(attachments.to_vec());
+        }
+
+        Ok(packet)
+    }
+
+    /// Reads the number of attachments from the header, the body of the
+    /// packet is decoded once all of its frames arrived.
+    fn frame_count(&self, frame: &Bytes) -> Result<usize> {
+        let packet = Packet::decode_header_only(frame)?;
+        Ok(usize::from(packet.attachment_count) + 1)
+    }
+}
+
+/// Splits packets into the frames of the default parser, the counterpart
+/// of [`Decoder`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Encoder;
+
+impl Encoder {
+    /// Encodes the header of the packet as text, followed by its attachments
+    /// in the order of their placeholders. Packets without attachments are
+    /// encoded into a single frame.
+    pub fn encode(packet: &Packet) -> Vec<Bytes> {
+        let mut frames = vec![Bytes::from(packet)];
+        if let Some(attachments) = packet.attachments.as_ref() {
+            frames.extend(attachments.iter().cloned());
+        }
+        frames
+    }
+}
+
+/// Incrementally reassembles packets from the frames of the default parser.
+/// A binary packet is only emitted once the text frame carrying its header
+/// has been followed by all of its attachments.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    pending: VecDeque<Packet>,
+    max_attachments: u8,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder {
+            pending: VecDeque::new(),
+            max_attachments: Decoder::DEFAULT_MAX_ATTACHMENTS,
+        }
+    }
+}
+
+impl Decoder {
+    /// The number of attachments a packet may announce by default.
+    pub const DEFAULT_MAX_ATTACHMENTS: u8 = Packet::DEFAULT_MAX_ATTACHMENTS;
+
+    /// The number of binary packets that may wait for their attachments at
+    /// the same time.
+    pub const MAX_PENDING_PACKETS: usize = 8;
+
+    /// Creates a decoder that isn't waiting for any attachments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the number of attachments a packet may announce. Packets that
+    /// announce more fail with [`Error::TooManyAttachments`], before any of
+    /// their attachments are buffered. Decoding a single frame, e.g. with
+    /// `Packet::try_from`, always uses [`Packet::DEFAULT_MAX_ATTACHMENTS`].
+    pub fn set_max_attachments(&mut self, max_attachments: u8) {
+        self.max_attachments = max_attachments;
+    }
+
+    /// Feeds a text frame into the decoder. Returns the packet right away
+    /// unless it announces attachments. The header of a binary packet that
+    /// arrives while an earlier one is still missing attachments is queued
+    /// behind it, and attachments are assigned in the order of the headers.
+    /// Once [`Decoder::MAX_PENDING_PACKETS`] packets are waiting, further
+    /// headers fail with [`Error::UnexpectedHeaderDuringReassembly`] and the
+    /// waiting packets are kept.
+    pub fn push_text(&mut self, frame: Bytes) -> Result<Option<Packet>> {
+        let mut packet = Packet::default();
+        packet.decode_into_limited(&frame, self.max_attachments)?;
+        if packet.attachment_count == 0 {
+            return Ok(Some(packet));
+        }
+
+        if self.pending.len() >= Decoder::MAX_PENDING_PACKETS {
+            let missing = self
+                .pending
+                .iter()
+                .map(|packet| {
+                    let received = packet.attachments.as_ref().map_or(0, Vec::len);
+                    usize::from(packet.attachment_count) - received
+                })
+                .sum();
+            return Err(Error::UnexpectedHeaderDuringReassembly(missing));
+        }
+
+        packet.attachments = Some(Vec::with_capacity(usize::from(packet.attachment_count)));
+        self.pending.push_back(packet);
+
+        Ok(None)
+    }
+
+    /// Feeds a binary frame into the decoder, which has to be an attachment
+    /// of the oldest packet that is waiting for attachments. Returns that
+    /// packet once its last attachment arrived.
+    pub fn push_binary(&mut self, frame: Bytes) -> Result<Option<Packet>> {
+        let packet = self.pending.front_mut().ok_or(Error::InvalidPacket())?;
+
+        let attachments = packet.attachments.get_or_insert_with(Vec::new);
+        attachments.push(frame);
+        if attachments.len() < usize::from(packet.attachment_count) {
+            return Ok(None);
+        }
+
+        Ok(self.pending.pop_front())
+    }
+
+    /// Returns whether the decoder is waiting for attachments.
+    pub fn is_reassembling(&self) -> bool {
+        !self.pending.is_empty()
+    }
+}
+
+/// Lazily decodes packets from an iterator over the raw frames of the
+/// default parser. The frames that follow a binary packet are taken as its
+/// attachments. After the first error the stream ends.
+#[derive(Debug)]
+pub struct PacketStream<I> {
+    frames: I,
+    decoder: Decoder,
+    done: bool,
+}
+
+impl<I: Iterator<Item = Bytes>> PacketStream<I> {
+    /// Creates a stream over the packets in `frames`.
+    pub fn new(frames: I) -> Self {
+        PacketStream {
+            frames,
+            decoder: Decoder::new(),
+            done: false,
+        }
+    }
+}
+
+impl<I: Iterator<Item = Bytes>> Iterator for PacketStream<I> {
+    type Item = Result<Packet>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let Some(frame) = self.frames.next() else {
+                self.done = true;
+                // the frames ended in the middle of a binary packet
+                return self
+                    .decoder
+                    .is_reassembling()
+                    .then_some(Err(Error::IncompletePacket(
+                        IncompleteReason::UnexpectedEnd,
+                    )));
+            };
+
+            let result = if self.decoder.is_reassembling() {
+                self.decoder.push_binary(frame)
+            } else {
+                self.decoder.push_text(frame)
+            };
+
+            match result {
+                Ok(Some(packet)) => return Some(Ok(packet)),
+                Ok(None) => continue,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+impl<I: Iterator<Item = Bytes>> FusedIterator for PacketStream<I> {}
+
+#[cfg(feature = "msgpack")]
+pub use msgpack::MsgpackParser;
+
+#[cfg(feature = "msgpack")]
+mod msgpack {
+    use super::Parser;
+    use crate::error::{Error, IncompleteReason, Result};
+    use crate::packet::{Packet, PacketId};
+    use bytes::Bytes;
+    use rmpv::Value as MsgpackValue;
+    use serde_json::Value;
+    use std::convert::TryFrom;
+
+    /// A parser compatible with `socket.io-msgpack-parser`. Every packet is
+    /// encoded as a single MessagePack map, binary data is embedded into
+    /// it instead of being sent as separate attachments.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct MsgpackParser;
+
+    impl Parser for MsgpackParser {
+        /// Encodes the packet into a single frame. Data that is not valid
+        /// JSON is sent as a plain string.
+        fn encode(&self, packet: &Packet) -> Vec<Bytes> {
+            let (packet_type, data) = match packet.packet_type {
+                PacketId::BinaryEvent | PacketId::BinaryAck => {
+                    // binary packets store their data without the surrounding brackets
+                    let data = packet.data.as_deref().unwrap_or_default();
+                    let mut values = match parse(&format!("[{data}]")) {
+                        MsgpackValue::Array(values) => values,
+                        value => vec![value],
+                    };
+                    values.extend(
+                        packet
+                            .attachments
+                            .iter()
+                            .flatten()
+                            .map(|attachment| MsgpackValue::Binary(attachment.to_vec())),
+                    );
+
+                    let packet_type = if packet.packet_type == PacketId::BinaryEvent {
+                        PacketId::Event
+                    } else {
+                        PacketId::Ack
+                    };
+                    (packet_type, Some(MsgpackValue::Array(values)))
+                }
+                packet_type => (packet_type, packet.data.as_deref().map(parse)),
+            };
+
+            let mut fields = vec![
+                (
+                    MsgpackValue::from("type"),
+                    MsgpackValue::from(packet_type as u8),
+                ),
+                (
+                    MsgpackValue::from("nsp"),
+                    MsgpackValue::from(packet.nsp.as_str()),
+                ),
+            ];
+            if let Some(data) = data {
+                fields.push((MsgpackValue::from("data"), data));
+            }
+            if let Some(id) = packet.id {
+                fields.push((MsgpackValue::from("id"), MsgpackValue::from(id)));
+            }
+
+            let mut buffer = Vec::new();
+            rmpv::encode::write_value(&mut buffer, &MsgpackValue::Map(fields))
+                .expect("writing to a Vec never fails");
+
+            vec![Bytes::from(buffer)]
+        }
+
+        fn is_binary_frame(&self, _index: usize) -> bool {
+            true
+        }
+
+        fn decode(&self, frames: &[Bytes]) -> Result<Packet> {
+            let [frame] = frames else {
+                return Err(Error::InvalidPacket());
+            };
+
+            let MsgpackValue::Map(fields) =
+                rmpv::decode::read_value(&mut &frame[..]).map_err(|_| Error::InvalidPacket())?
+            else {
+                return Err(Error::InvalidPacket());
+            };
+
+            let mut packet = Packet::default();
+            let mut packet_type = None;
+            let mut data = None;
+
+            for (key, value) in fields {
+                match key.as_str() {
+                    Some("type") => packet_type = value.as_u64(),
+                    Some("nsp") => {
+                        packet.nsp = value.as_str().ok_or(Error::InvalidPacket())?.to_owned()
+                    }
+                    Some("data") => data = Some(value),
+                    Some("id") => {
+                        let id = value.as_i64().ok_or(Error::InvalidPacket())?;
+                        packet.id = Some(i32::try_from(id).map_err(|_| Error::InvalidPacket())?);
+                    }
+                    _ => (),
+                }
+            }
+
+            let packet_type =
+                packet_type.ok_or(Error::IncompletePacket(IncompleteReason::UnexpectedEnd))?;
+            let packet_type = u32::try_from(packet_type)
+                .ok()
+                .and_then(|packet_type| char::from_digit(packet_type, 10))
+                .ok_or(Error::InvalidPacket())?;
+            packet.packet_type = PacketId::try_from(packet_type)?;
+
+            match data {
+                None | Some(MsgpackValue::Nil) => (),
+                Some(MsgpackValue::Array(values))
+                    if matches!(packet.packet_type, PacketId::Event | PacketId::Ack) =>
+                {
+                    let (attachments, values): (Vec<_>, Vec<_>) = values
+                        .into_iter()
+                        .partition(|value| matches!(value, MsgpackValue::Binary(_)));
+                    let values = values
+                        .into_iter()
+                        .map(to_json)
+                        .collect::<Result<Vec<_>>>()?;
+
+                    if attachments.is_empty() {
+                        packet.data = Some(Value::Array(values).to_string());
+                    } else {
+                        packet.packet_type = if packet.packet_type == PacketId::Event {
+                            PacketId::BinaryEvent
+                        } else {
+                            PacketId::BinaryAck
+                        };
+                        packet.attachment_count =
+                            u8::try_from(attachments.len()).map_err(|_| Error::InvalidPacket())?;
+                        packet.attachments = Some(
+                            attachments
+                                .into_iter()
+                                .filter_map(|attachment| match attachment {
+                                    MsgpackValue::Binary(data) => Some(Bytes::from(data)),
+                                    _ => None,
+                                })
+                                .collect(),
+                        );
+
+                        // binary packets store their data without the surrounding brackets
+                        let data = values
+                            .iter()
+                            .map(Value::to_string)
+                            .collect::<Vec<_>>()
+                            .join(",");
+                        if !data.is_empty() {
+                            packet.data = Some(data);
+                        }
+                    }
+                }
+                Some(value) => packet.data = Some(to_json(value)?.to_string()),
+            }
+
+            Ok(packet)
+        }
+    }
+
+    /// Parses JSON data into its MessagePack representation, falling back
+    /// to a plain string if it isn't valid JSON.
+    fn parse(data: &str) -> MsgpackValue {
+        match serde_json::from_str(data) {
+            Ok(value) => to_msgpack(value),
+            Err(_) => MsgpackValue::from(data),
+        }
+    }
+
+    fn to_msgpack(value: Value) -> MsgpackValue {
+        match value {
+            Value::Null => MsgpackValue::Nil,
+            Value::Bool(value) => MsgpackValue::Boolean(value),
+            Value::Number(number) => {
+                if let Some(number) = number.as_i64() {
+                    MsgpackValue::from(number)
+                } else if let Some(number) = number.as_u64() {
+                    MsgpackValue::from(number)
+                } else {
+                    MsgpackValue::F64(number.as_f64().unwrap_or_default())
+                }
+            }
+            Value::String(value) => MsgpackValue::from(value),
+            Value::Array(values) => {
+                MsgpackValue::Array(values.into_iter().map(to_msgpack).collect())
+            }
+            Value::Object(map) => MsgpackValue::Map(
+                map.into_iter()
+                    .map(|(key, value)| (MsgpackValue::from(key), to_msgpack(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Converts a MessagePack value into JSON. Binary data is only supported
+    /// as a top level argument of an event or ack.
+    fn to_json(value: MsgpackValue) -> Result<Value> {
+        Ok(match value {
+            MsgpackValue::Nil => Value::Null,
+            MsgpackValue::Boolean(value) => Value::Bool(value),
+            MsgpackValue::Integer(number) => number
+                .as_i64()
+                .map(Value::from)
+                .or_else(|| number.as_u64().map(Value::from))
+                .ok_or(Error::InvalidPacket())?,
+            MsgpackValue::F32(number) => Value::from(f64::from(number)),
+            MsgpackValue::F64(number) => Value::from(number),
+            MsgpackValue::String(value) => {
+                Value::String(value.into_str().ok_or(Error::InvalidPacket())?)
+            }
+            MsgpackValue::Array(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(to_json)
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+            MsgpackValue::Map(map) => Value::Object(
+                map.into_iter()
+                    .map(|(key, value)| {
+                        let key = key.as_str().ok_or(Error::InvalidPacket())?.to_owned();
+                        Ok((key, to_json(value)?))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+            MsgpackValue::Binary(_) | MsgpackValue::Ext(..) => return Err(Error::InvalidPacket()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::packet::PacketId;
+
+    fn packets() -> Vec<Packet> {
+        vec![
+            Packet::new(
+                PacketId::Connect,
+                "/admin".to_owned(),
+                Some(String::from("{\"token\":\"123\"}")),
+                None,
+                0,
+                None,
+            ),
+            Packet::new(
+                PacketId::Disconnect,
+                "/admin".to_owned(),
+                None,
+                None,
+                0,
+                None,
+            ),
+            Packet::new(
+                PacketId::Event,
+                "/admin".to_owned(),
+                Some(String::from("[\"project:delete\",123]")),
+                Some(456),
+                0,
+                None,
+            ),
+            Packet::new(
+                PacketId::Ack,
+                "/".to_owned(),
+                Some(String::from("[]")),
+                Some(456),
+                0,
+                None,
+            ),
+            Packet::new(
+                PacketId::BinaryEvent,
+                "/admin".to_owned(),
+                Some(String::from("\"project:delete\"")),
+                Some(456),
+                1,
+                Some(vec![Bytes::from_static(&[1, 2, 3])]),
+            ),
+            Packet::new(
+                PacketId::BinaryAck,
+                "/admin".to_owned(),
+                None,
+                Some(456),
+                1,
+                Some(vec![Bytes::from_static(&[3, 2, 1])]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_default_parser_round_trip() {
+        for packet in packets() {
+            let frames = DefaultParser.encode(&packet);
+
+            // the same frames the socket sends today
+            assert_eq!(frames[0], Bytes::from(&packet));
+            assert_eq!(
+                frames[1..].to_vec(),
+                packet.attachments.clone().unwrap_or_default()
+            );
+
+            assert_eq!(DefaultParser.decode(&frames).unwrap(), packet);
+
+            // the socket collects this many frames before decoding them
+            assert_eq!(DefaultParser.frame_count(&frames[0]).unwrap(), frames.len());
+            assert!(!DefaultParser.is_binary_frame(0));
+            assert!(DefaultParser.is_binary_frame(1));
+        }
+    }
+
+    #[test]
+    fn test_default_parser_decode_errors() {
+        assert!(matches!(
+            DefaultParser.decode(&[]),
+            Err(Error::IncompletePacket(IncompleteReason::EmptyInput))
+        ));
+
+        // the attachment is missing
+        let frames = [Bytes::from_static(
+            b"51-[\"hello\",{\"_placeholder\":true,\"num\":0}]",
+        )];
+        assert!(matches!(
+            DefaultParser.decode(&frames),
+            Err(Error::InvalidPacket())
+        ));
+    }
+
+    #[test]
+    fn test_encoder() {
+        for packet in packets() {
+            let frames = Encoder::encode(&packet);
+            assert_eq!(frames.len(), 1 + usize::from(packet.attachment_count));
+        }
+
+        let attachments = (1..=2).map(|i| Bytes::from(vec![i; 3]));
+        let packet = Packet::binary_event_from_iter("hello", "/admin", attachments).unwrap();
+        assert_eq!(
+            Encoder::encode(&packet),
+            vec![
+                Bytes::from_static(
+                    b"52-/admin,[\"hello\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]"
+                ),
+                Bytes::from_static(&[1, 1, 1]),
+                Bytes::from_static(&[2, 2, 2]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decoder_binary_event() {
+        let mut decoder = Decoder::new();
+
+        let header = Bytes::from_static(
+            b"52-/admin,[\"hello\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]",
+        );
+        assert_eq!(decoder.push_text(header).unwrap(), None);
+        assert!(decoder.is_reassembling());
+
+        assert_eq!(
+            decoder.push_binary(Bytes::from_static(&[1, 2])).unwrap(),
+            None
+        );
+        let packet = decoder
+            .push_binary(Bytes::from_static(&[3, 4]))
+            .unwrap()
+            .unwrap();
+        assert!(!decoder.is_reassembling());
+
+        assert_eq!(packet.packet_type, PacketId::BinaryEvent);
+        assert_eq!(packet.nsp, "/admin");
+        assert_eq!(packet.attachment_count, 2);
+        assert_eq!(
+            packet.attachments,
+            Some(vec![
+                Bytes::from_static(&[1, 2]),
+                Bytes::from_static(&[3, 4])
+            ])
+        );
+
+        // packets without attachments are emitted right away
+        let packet = decoder
+            .push_text(Bytes::from_static(b"2[\"hello\"]"))
+            .unwrap();
+        assert_eq!(packet.unwrap().packet_type, PacketId::Event);
+    }
+
+    #[test]
+    fn test_packet_stream() {
+        let frames = vec![
+            Bytes::from_static(b"2[\"hello\"]"),
+            Bytes::from_static(b"51-[\"upload\",{\"_placeholder\":true,\"num\":0}]"),
+            Bytes::from_static(&[1, 2, 3]),
+            Bytes::from_static(b"3/admin,1[]"),
+            Bytes::from_static(b"9"),
+            Bytes::from_static(b"2[\"ignored\"]"),
+        ];
+        let mut stream = PacketStream::new(frames.into_iter());
+
+        let packet = stream.next().unwrap().unwrap();
+        assert_eq!(packet.packet_type, PacketId::Event);
+        assert_eq!(packet.data.as_deref(), Some("[\"hello\"]"));
+
+        let packet = stream.next().unwrap().unwrap();
+        assert_eq!(packet.packet_type, PacketId::BinaryEvent);
+        assert_eq!(packet.data.as_deref(), Some("\"upload\""));
+        assert_eq!(
+            packet.attachments,
+            Some(vec![Bytes::from_static(&[1, 2, 3])])
+        );
+
+        let packet = stream.next().unwrap().unwrap();
+        assert_eq!(packet.packet_type, PacketId::Ack);
+        assert_eq!(packet.nsp, "/admin");
+        assert_eq!(packet.id, Some(1));
+
+        // the first error ends the stream
+        assert!(matches!(
+            stream.next(),
+            Some(Err(Error::InvalidPacketId('9')))
+        ));
+        assert!(stream.next().is_none());
+
+        let frames = vec![Bytes::from_static(
+            b"51-[\"upload\",{\"_placeholder\":true,\"num\":0}]",
+        )];
+        let mut stream = PacketStream::new(frames.into_iter());
+        assert!(matches!(
+            stream.next(),
+            Some(Err(Error::IncompletePacket(
+                IncompleteReason::UnexpectedEnd
+            )))
+        ));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_decoder_errors() {
+        let mut decoder = Decoder::new();
+
+        // there is no packet waiting for attachments
+        assert!(matches!(
+            decoder.push_binary(Bytes::from_static(&[1])),
+            Err(Error::InvalidPacket())
+        ));
+
+        // the queue of packets waiting for attachments is full
+        let header = Bytes::from_static(b"51-[\"hello\",{\"_placeholder\":true,\"num\":0}]");
+        for _ in 0..Decoder::MAX_PENDING_PACKETS {
+            assert_eq!(decoder.push_text(header.clone()).unwrap(), None);
+        }
+        assert!(matches!(
+            decoder.push_text(header),
+            Err(Error::UnexpectedHeaderDuringReassembly(missing))
+                if missing == Decoder::MAX_PENDING_PACKETS
+        ));
+
+        // the waiting packets are kept, and the decoder recovers once they
+        // are complete
+        for _ in 0..Decoder::MAX_PENDING_PACKETS {
+            let packet = decoder.push_binary(Bytes::from_static(&[1])).unwrap();
+            assert_eq!(packet.unwrap().attachment_count, 1);
+        }
+        assert!(!decoder.is_reassembling());
+        let packet = decoder.push_text(Bytes::from_static(b"2[\"hello\"]"));
+        assert_eq!(packet.unwrap().unwrap().packet_type, PacketId::Event);
+    }
+
+    #[test]
+    fn test_decoder_max_attachments() {
+        let placeholders = (0..200)
+            .map(|num| format!("{{\"_placeholder\":true,\"num\":{num}}}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        let header = Bytes::from(format!("5200-[\"upload\",{placeholders}]"));
+
+        let mut decoder = Decoder::new();
+        decoder.set_max_attachments(16);
+        assert!(matches!(
+            decoder.push_text(header.clone()),
+            Err(Error::TooManyAttachments(200))
+        ));
+        assert!(!decoder.is_reassembling());
+
+        let mut decoder = Decoder::new();
+        decoder.set_max_attachments(200);
+        assert_eq!(decoder.push_text(header).unwrap(), None);
+        assert!(decoder.is_reassembling());
+    }
+
+    #[test]
+    fn test_decoder_interleaved_binary_events() {
+        let mut decoder = Decoder::new();
+
+        let first = Bytes::from_static(
+            b"52-[\"first\",{\"_placeholder\":true,\"num\":0},{\"_placeholder\":true,\"num\":1}]",
+        );
+        let second = Bytes::from_static(b"51-[\"second\",{\"_placeholder\":true,\"num\":0}]");
+
+        // the second header arrives before the first packet is complete
+        assert_eq!(decoder.push_text(first).unwrap(), None);
+        assert_eq!(decoder.push_binary(Bytes::from_static(&[1])).unwrap(), None);
+        assert_eq!(decoder.push_text(second).unwrap(), None);
+
+        // packets without attachments don't wait for the pending ones
+        let packet = decoder.push_text(Bytes::from_static(b"2[\"plain\"]"));
+        assert_eq!(packet.unwrap().unwrap().packet_type, PacketId::Event);
+
+        // attachments complete the packets in the order of their headers
+        let packet = decoder
+            .push_binary(Bytes::from_static(&[2]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"first\""));
+        assert_eq!(
+            packet.attachments,
+            Some(vec![Bytes::from_static(&[1]), Bytes::from_static(&[2])])
+        );
+        assert!(decoder.is_reassembling());
+
+        let packet = decoder
+            .push_binary(Bytes::from_static(&[3]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(packet.data.as_deref(), Some("\"second\""));
+        assert_eq!(packet.attachments, Some(vec![Bytes::from_static(&[3])]));
+        assert!(!decoder.is_reassembling());
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_parser_round_trip() {
+        for packet in packets() {
+            let frames = MsgpackParser.encode(&packet);
+            assert_eq!(frames.len(), 1);
+            assert_eq!(MsgpackParser.frame_count(&frames[0]).unwrap(), 1);
+            assert!(MsgpackParser.is_binary_frame(0));
+            assert_eq!(MsgpackParser.decode(&frames).unwrap(), packet);
+        }
+    }
+}
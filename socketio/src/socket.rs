@@ -1,30 +1,34 @@
-use crate::error::{Error, Result};
+use crate::error::{Error, IncompleteReason, Result};
 use crate::packet::{Packet, PacketId};
-use bytes::Bytes;
+use crate::parser::Parser;
 use rust_engineio::{Client as EngineClient, Packet as EnginePacket, PacketId as EnginePacketId};
 use serde::de::IgnoredAny;
 use serde_json::Value;
-use std::convert::TryFrom;
 use std::sync::{atomic::AtomicBool, Arc};
 use std::{fmt::Debug, sync::atomic::Ordering};
 
 use super::{event::Event, payload::Payload};
 
 /// Handles communication in the `socket.io` protocol.
-#[derive(Clone, Debug)]
+#[derive(Clone)]
 pub(crate) struct Socket {
     //TODO: 0.4.0 refactor this
     engine_client: Arc<EngineClient>,
     connected: Arc<AtomicBool>,
+    parser: Arc<dyn Parser + Send + Sync>,
 }
 
 impl Socket {
     /// Creates an instance of `Socket`.
 
-    pub(super) fn new(engine_client: EngineClient) -> Result<Self> {
+    pub(super) fn new(
+        engine_client: EngineClient,
+        parser: Arc<dyn Parser + Send + Sync>,
+    ) -> Result<Self> {
         Ok(Socket {
             engine_client: Arc::new(engine_client),
             connected: Arc::new(AtomicBool::default()),
+            parser,
         })
     }
 
@@ -58,15 +62,16 @@ impl Socket {
             return Err(Error::IllegalActionBeforeOpen());
         }
 
-        // the packet, encoded as an engine.io message packet
-        let engine_packet = EnginePacket::new(EnginePacketId::Message, Bytes::from(&packet));
-        self.engine_client.emit(engine_packet)?;
-
-        if let Some(attachments) = packet.attachments {
-            for attachment in attachments {
-                let engine_packet = EnginePacket::new(EnginePacketId::MessageBinary, attachment);
-                self.engine_client.emit(engine_packet)?;
-            }
+        // the parser decides which frames are sent as engine.io message
+        // packets and which as binary ones
+        for (idx, frame) in self.parser.encode(&packet).into_iter().enumerate() {
+            let packet_id = if self.parser.is_binary_frame(idx) {
+                EnginePacketId::MessageBinary
+            } else {
+                EnginePacketId::Message
+            };
+            self.engine_client
+                .emit(EnginePacket::new(packet_id, frame))?;
         }
 
         Ok(())
@@ -163,40 +168,42 @@ impl Socket {
 
     /// Handles new incoming engineio packets
     fn handle_engineio_packet(&self, packet: EnginePacket) -> Result<Packet> {
-        let mut socket_packet = Packet::try_from(&packet.data)?;
+        let frame_count = self.parser.frame_count(&packet.data)?;
+        let mut frames = vec![packet.data];
 
         // Only handle attachments if there are any
-        if socket_packet.attachment_count > 0 {
-            let mut attachments_left = socket_packet.attachment_count;
-            let mut attachments = Vec::new();
-            while attachments_left > 0 {
-                let next = self.engine_client.poll();
-                match next {
-                    Err(err) => return Err(err.into()),
-                    Ok(Some(packet)) => match packet.packet_id {
-                        EnginePacketId::MessageBinary | EnginePacketId::Message => {
-                            attachments.push(packet.data);
-                            attachments_left -= 1;
-                        }
-                        _ => {
-                            return Err(Error::InvalidAttachmentPacketType(
-                                packet.packet_id.into(),
-                            ));
-                        }
-                    },
-                    Ok(None) => {
-                        // Engineio closed before attachments completed.
-                        return Err(Error::IncompletePacket());
+        while frames.len() < frame_count {
+            let next = self.engine_client.poll();
+            match next {
+                Err(err) => return Err(err.into()),
+                Ok(Some(packet)) => match packet.packet_id {
+                    EnginePacketId::MessageBinary | EnginePacketId::Message => {
+                        frames.push(packet.data);
                     }
+                    _ => {
+                        return Err(Error::InvalidAttachmentPacketType(packet.packet_id.into()));
+                    }
+                },
+                Ok(None) => {
+                    // Engineio closed before attachments completed.
+                    return Err(Error::IncompletePacket(IncompleteReason::UnexpectedEnd));
                 }
             }
-            socket_packet.attachments = Some(attachments);
         }
 
-        Ok(socket_packet)
+        self.parser.decode(&frames)
     }
 
     fn is_engineio_connected(&self) -> Result<bool> {
         Ok(self.engine_client.is_connected()?)
     }
 }
+
+impl Debug for Socket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Socket")
+            .field("engine_client", &self.engine_client)
+            .field("connected", &self.connected)
+            .finish()
+    }
+}